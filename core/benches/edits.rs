@@ -0,0 +1,63 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use magic_string::{GenerateDecodedMapOptions, MagicString};
+
+const EDITS: usize = 10_000;
+
+// A generated file of 50k lines, the kind of input codemods apply scattered edits to.
+fn create_source() -> String {
+  (0..50_000)
+    .map(|i| format!("export const foo_{} = {};\n", i, i))
+    .collect()
+}
+
+// Xorshift, so edits are random but the same for every run.
+struct Random(u64);
+
+impl Random {
+  fn next(&mut self, max: u32) -> u32 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    (self.0 % max as u64) as u32
+  }
+}
+
+fn apply_random_edits(source: &str) -> MagicString {
+  let mut s = MagicString::new(source);
+  let mut random = Random(0x2545_f491_4f6c_dd1d);
+  let len = source.len() as u32;
+
+  for i in 0..EDITS {
+    let index = random.next(len - 1);
+    match i % 3 {
+      0 => s.append_left(index, "/* left */").unwrap(),
+      1 => s.prepend_right(index, "/* right */").unwrap(),
+      _ => s.remove(index as i64, index as i64 + 1).unwrap(),
+    };
+  }
+
+  s
+}
+
+fn random_edits(c: &mut Criterion) {
+  let source = create_source();
+
+  c.bench_function("apply 10k random edits", |b| {
+    b.iter(|| apply_random_edits(black_box(source.as_str())))
+  });
+
+  let s = apply_random_edits(source.as_str());
+  c.bench_function("to_string after 10k random edits", |b| {
+    b.iter(|| black_box(&s).to_string())
+  });
+  c.bench_function("generate_map after 10k random edits", |b| {
+    b.iter(|| {
+      black_box(&s)
+        .generate_map(GenerateDecodedMapOptions::default())
+        .unwrap()
+    })
+  });
+}
+
+criterion_group!(benches, random_edits);
+criterion_main!(benches);
@@ -0,0 +1,414 @@
+use std::{collections::HashMap, fmt};
+
+#[cfg(feature = "node-api")]
+use napi_derive::napi;
+
+use crate::{
+  magic_string::{DecodedMap, GenerateDecodedMapOptions, Hires, MagicString},
+  mapping::{Mapping, Mappings, MappingsWriter},
+  result::Result,
+  source_map::{Section, SectionOffset, SectionedSourceMap, SourceMap, SourceMappingUrlStyle},
+  utils::get_relative_path,
+  Error, MagicStringErrorType,
+};
+
+#[cfg(feature = "node-api")]
+#[napi(object)]
+#[derive(Debug, Default, Clone)]
+pub struct BundleOptions {
+  pub intro: Option<String>,
+  pub separator: Option<String>,
+}
+
+#[cfg(not(feature = "node-api"))]
+#[derive(Debug, Default, Clone)]
+pub struct BundleOptions {
+  pub intro: Option<String>,
+  pub separator: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct BundleSource {
+  filename: Option<String>,
+  content: MagicString,
+  separator: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct UniqueSource {
+  filename: String,
+  content: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bundle {
+  intro: String,
+  separator: String,
+
+  sources: Vec<BundleSource>,
+
+  unique_sources: Vec<UniqueSource>,
+  unique_source_index_by_filename: HashMap<String, usize>,
+}
+
+impl Bundle {
+  /// ## Create a new `Bundle` instance
+  ///
+  /// A `Bundle` concatenates multiple `MagicString`s into one output, separated by `separator` (defaults to `\n`).
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{Bundle, BundleOptions, MagicString};
+  ///
+  /// let mut bundle = Bundle::new(BundleOptions::default());
+  ///
+  /// bundle.add_source(Some("a.js"), MagicString::new("const a = 1"));
+  /// bundle.add_source(Some("b.js"), MagicString::new("const b = 2"));
+  ///
+  /// assert_eq!(bundle.to_string(), "const a = 1\nconst b = 2");
+  /// ```
+  pub fn new(options: BundleOptions) -> Bundle {
+    Bundle {
+      intro: options.intro.unwrap_or_default(),
+      separator: options.separator.unwrap_or_else(|| String::from("\n")),
+
+      sources: Vec::default(),
+
+      unique_sources: Vec::default(),
+      unique_source_index_by_filename: HashMap::default(),
+    }
+  }
+
+  /// ## Add source
+  ///
+  /// Adds a `MagicString` to the bundle. Sources with a `filename` will be mapped in the generated sourcemap,
+  /// and sources sharing the same `filename` must have the same original content. Returns `self`.
+  pub fn add_source(&mut self, filename: Option<&str>, content: MagicString) -> Result<&mut Self> {
+    self._add_source(filename, content, None)
+  }
+
+  /// ## Append
+  ///
+  /// Appends the specified content to the end of the bundle, separated by `separator` (defaults to an empty string). Returns `self`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{Bundle, BundleOptions, MagicString};
+  ///
+  /// let mut bundle = Bundle::new(BundleOptions::default());
+  ///
+  /// bundle.add_source(Some("a.js"), MagicString::new("const a = 1"));
+  /// bundle.append("\nexport { a }", None);
+  ///
+  /// assert_eq!(bundle.to_string(), "const a = 1\nexport { a }");
+  /// ```
+  pub fn append(&mut self, str: &str, separator: Option<&str>) -> Result<&mut Self> {
+    self._add_source(
+      None,
+      MagicString::new(str),
+      Some(separator.unwrap_or_default().to_owned()),
+    )
+  }
+
+  /// ## Prepend
+  ///
+  /// Prepends the bundle with the specified content. Returns `self`.
+  pub fn prepend(&mut self, str: &str) -> Result<&mut Self> {
+    self.intro.insert_str(0, str);
+
+    Ok(self)
+  }
+
+  /// ## Generate decoded map
+  ///
+  /// Generates a sourcemap object with raw mappings in array form, each added source with a `filename` is listed in `sources` only once.
+  /// `source` of `GenerateDecodedMapOptions` is ignored, as sources are taken from the `filename` of each added source.
+  /// If `file` is specified, `sources` are relative to it.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{Bundle, BundleOptions, GenerateDecodedMapOptions, MagicString};
+  ///
+  /// let mut bundle = Bundle::new(BundleOptions::default());
+  ///
+  /// bundle.add_source(Some("src/a.js"), MagicString::new("const a = 1"));
+  /// bundle.add_source(Some("src/b.js"), MagicString::new("const b = 2"));
+  ///
+  /// let map = bundle.generate_decoded_map(GenerateDecodedMapOptions {
+  ///   file: Some("dist/bundle.js".to_owned()),
+  ///   ..GenerateDecodedMapOptions::default()
+  /// }).unwrap();
+  ///
+  /// assert_eq!(map.sources, vec![Some("../src/a.js".to_owned()), Some("../src/b.js".to_owned())]);
+  /// assert_eq!(map.mappings, vec![vec![vec![0, 0, 0, 0]], vec![vec![0, 1, 0, 0]]]);
+  /// ```
+  pub fn generate_decoded_map(&self, options: GenerateDecodedMapOptions) -> Result<DecodedMap> {
+    let names = self._names();
+    let mut map = Mapping::new(options.hires, options.column_unit);
+
+    self._add_to_mapping(&mut map, names.as_slice())?;
+
+    Ok(self._decoded_map(options, names, map.get_decoded_mappings()))
+  }
+
+  /// ## Generate Map
+  ///
+  /// Generates a version 3 sourcemap of the bundle, whose mappings are encoded while walking through the sources.
+  /// See `generate_decoded_map` for detailed document.
+  pub fn generate_map(&self, options: GenerateDecodedMapOptions) -> Result<SourceMap> {
+    let names = self._names();
+    let mut map = Mapping::new_encoded(options.hires, options.column_unit);
+
+    self._add_to_mapping(&mut map, names.as_slice())?;
+
+    Ok(SourceMap::new_with_encoded_mappings(
+      map.into_encoded_mappings(),
+      self._decoded_map(options, names, Mappings::default()),
+    ))
+  }
+
+  /// ## Finalize
+  ///
+  /// Generates a sourcemap of the bundle, and returns the concatenated string with the comments referring to the sourcemap appended,
+  /// along with the sourcemap itself. See `MagicString::finalize` for detailed document.
+  pub fn finalize(
+    &self,
+    options: GenerateDecodedMapOptions,
+    url_style: SourceMappingUrlStyle,
+  ) -> Result<(String, SourceMap)> {
+    let map = self.generate_map(options)?;
+    let mut code = self.to_string();
+
+    url_style.append_comments(&mut code, &map)?;
+
+    Ok((code, map))
+  }
+
+  /// ## Generate sectioned map
+  ///
+  /// Generates a version 3 index sourcemap of the bundle, which has a section for each added source with a `filename`,
+  /// starting at the generated position of the source. This avoids merging mappings of all sources into one large string.
+  /// Options are applied to the sourcemap of each section except `debug_id`, see `generate_decoded_map` for detailed document.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{Bundle, BundleOptions, GenerateDecodedMapOptions, MagicString};
+  ///
+  /// let mut bundle = Bundle::new(BundleOptions::default());
+  ///
+  /// bundle.add_source(Some("a.js"), MagicString::new("const a = 1"));
+  /// bundle.add_source(Some("b.js"), MagicString::new("const b = 2"));
+  ///
+  /// let map = bundle.generate_sectioned_map(GenerateDecodedMapOptions::default()).unwrap();
+  ///
+  /// assert_eq!(map.sections[1].offset.line, 1);
+  /// assert_eq!(map.sections[1].map.sources, vec![Some("b.js".to_owned())]);
+  /// assert_eq!(map.flatten().unwrap().mappings, bundle.generate_map(GenerateDecodedMapOptions::default()).unwrap().mappings);
+  /// ```
+  pub fn generate_sectioned_map(
+    &self,
+    options: GenerateDecodedMapOptions,
+  ) -> Result<SectionedSourceMap> {
+    let mut sections: Vec<Section> = vec![];
+    // only used to track the generated position of each source
+    let mut map = Mapping::new(Hires::Off, options.column_unit);
+
+    map.advance(self.intro.as_str());
+
+    for (i, source) in self.sources.iter().enumerate() {
+      if i > 0 {
+        map.advance(
+          source
+            .separator
+            .as_deref()
+            .unwrap_or(self.separator.as_str()),
+        );
+      }
+
+      if let Some(ref filename) = source.filename {
+        let (line, column) = map.generated_position();
+
+        sections.push(Section {
+          offset: SectionOffset { line, column },
+          map: source.content.generate_map(GenerateDecodedMapOptions {
+            file: None,
+            source: Some(match options.file {
+              Some(ref file) => get_relative_path(file.as_str(), filename.as_str()),
+              None => filename.to_owned(),
+            }),
+            // `ignore_list` of the options refers to the sources of the whole bundle
+            ignore_list: if options
+              .ignore_list
+              .contains(&(self.unique_source_index_by_filename[filename] as u32))
+            {
+              vec![0]
+            } else {
+              vec![]
+            },
+            debug_id: None,
+            ..options.clone()
+          })?,
+        });
+      }
+
+      map.advance(source.content.to_string().as_str());
+    }
+
+    Ok(SectionedSourceMap::new(
+      options
+        .file
+        .as_deref()
+        .map(|file| file.rsplit(['/', '\\']).next().unwrap_or_default()),
+      sections,
+    ))
+  }
+
+  // Stored names of all sources, without duplicates.
+  fn _names(&self) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for source in self.sources.iter() {
+      for name in source.content.stored_names.iter() {
+        if !names.contains(name) {
+          names.push(name.to_owned());
+        }
+      }
+    }
+
+    names
+  }
+
+  fn _add_to_mapping<W: MappingsWriter>(&self, map: &mut Mapping<W>, names: &[String]) -> Result {
+    map.advance(self.intro.as_str());
+
+    for (i, source) in self.sources.iter().enumerate() {
+      if i > 0 {
+        map.advance(
+          source
+            .separator
+            .as_deref()
+            .unwrap_or(self.separator.as_str()),
+        );
+      }
+
+      match source.filename {
+        Some(ref filename) => {
+          let source_index = self.unique_source_index_by_filename[filename];
+          source
+            .content
+            .add_to_mapping(map, source_index as u32, names)?;
+        }
+        None => map.advance(source.content.to_string().as_str()),
+      }
+    }
+
+    Ok(())
+  }
+
+  // The decoded map of the bundle with the given names and mappings.
+  fn _decoded_map(
+    &self,
+    options: GenerateDecodedMapOptions,
+    names: Vec<String>,
+    mappings: Mappings,
+  ) -> DecodedMap {
+    DecodedMap {
+      file: options.file.as_ref().map(|file| {
+        file
+          .rsplit(['/', '\\'])
+          .next()
+          .unwrap_or_default()
+          .to_owned()
+      }),
+      mappings,
+      source_root: options.source_root.to_owned(),
+      sources: self
+        .unique_sources
+        .iter()
+        .map(|source| match options.file {
+          Some(ref file) => Some(get_relative_path(file.as_str(), source.filename.as_str())),
+          None => Some(source.filename.to_owned()),
+        })
+        .collect(),
+      names,
+      sources_content: self
+        .unique_sources
+        .iter()
+        .map(|source| {
+          if options.include_content {
+            Some(source.content.to_owned())
+          } else {
+            None
+          }
+        })
+        .collect(),
+      ignore_list: options.ignore_list,
+      debug_id: options.debug_id,
+    }
+  }
+
+  fn _add_source(
+    &mut self,
+    filename: Option<&str>,
+    content: MagicString,
+    separator: Option<String>,
+  ) -> Result<&mut Self> {
+    if let Some(filename) = filename {
+      match self.unique_source_index_by_filename.get(filename) {
+        Some(&index) => {
+          if self.unique_sources[index].content != content.original_str {
+            return Err(Error::new_with_reason(
+              MagicStringErrorType::MagicStringIllegalSourceError,
+              format!(
+                "Illegal source: same filename ({}), different contents",
+                filename
+              )
+              .as_str(),
+            ));
+          }
+        }
+        None => {
+          self
+            .unique_source_index_by_filename
+            .insert(filename.to_owned(), self.unique_sources.len());
+          self.unique_sources.push(UniqueSource {
+            filename: filename.to_owned(),
+            content: content.original_str.to_owned(),
+          });
+        }
+      }
+    }
+
+    self.sources.push(BundleSource {
+      filename: filename.map(|f| f.to_owned()),
+      content,
+      separator,
+    });
+
+    Ok(self)
+  }
+}
+
+impl fmt::Display for Bundle {
+  /// ## To string
+  ///
+  /// Returns the concatenated string of the bundle.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.intro)?;
+
+    for (i, source) in self.sources.iter().enumerate() {
+      if i > 0 {
+        write!(
+          f,
+          "{}",
+          source
+            .separator
+            .as_deref()
+            .unwrap_or(self.separator.as_str())
+        )?;
+      }
+      source.content.write_to(f)?;
+    }
+
+    Ok(())
+  }
+}
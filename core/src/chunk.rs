@@ -1,7 +1,26 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+  fmt,
+  ops::{Index, IndexMut},
+};
 
-use crate::utils::trim;
-use crate::Result;
+// Index of a chunk in the arena, i.e. `chunks` of `MagicString`.
+// Chunks are never removed from the arena, so an id stays valid as long as the `MagicString` lives.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ChunkId(pub usize);
+
+impl Index<ChunkId> for Vec<Chunk> {
+  type Output = Chunk;
+
+  fn index(&self, id: ChunkId) -> &Chunk {
+    &self[id.0]
+  }
+}
+
+impl IndexMut<ChunkId> for Vec<Chunk> {
+  fn index_mut(&mut self, id: ChunkId) -> &mut Chunk {
+    &mut self[id.0]
+  }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Chunk {
@@ -14,8 +33,14 @@ pub struct Chunk {
   pub intro: String,
   pub outro: String,
 
-  pub next: Option<Rc<RefCell<Chunk>>>,
-  pub prev: Option<Rc<RefCell<Chunk>>>,
+  // index of the original content in the stored names of the `MagicString`, if it should be stored in the sourcemap `names` field
+  pub name_index: Option<usize>,
+
+  // start of the chunk holding the new content, if this chunk is a latter part of an overwritten range
+  pub overwrite_start: Option<u32>,
+
+  pub next: Option<ChunkId>,
+  pub prev: Option<ChunkId>,
 }
 
 impl Chunk {
@@ -29,6 +54,9 @@ impl Chunk {
       intro: String::default(),
       outro: String::default(),
 
+      name_index: None,
+      overwrite_start: None,
+
       next: None,
       prev: None,
     }
@@ -38,153 +66,110 @@ impl Chunk {
   // So I have to change this, please forgive me...
 
   pub fn append_outro(&mut self, content: &str) {
-    self.outro = format!("{}{}", self.outro, content);
+    self.outro.push_str(content);
   }
 
   pub fn prepend_outro(&mut self, content: &str) {
-    self.outro = format!("{}{}", content, self.outro);
+    self.outro.insert_str(0, content);
   }
 
   pub fn append_intro(&mut self, content: &str) {
-    self.intro = format!("{}{}", self.intro, content);
+    self.intro.push_str(content);
   }
 
   pub fn prepend_intro(&mut self, content: &str) {
-    self.intro = format!("{}{}", content, self.intro);
+    self.intro.insert_str(0, content);
   }
 
-  pub fn trim_start_regexp(&mut self, pat: &str) -> Result {
-    let trimmed_intro = trim::trim_start_regexp(self.intro.as_str(), pat)?.to_owned();
-    self.intro = trimmed_intro.to_owned();
-    if !trimmed_intro.is_empty() {
-      return Ok(());
-    }
+  pub fn reset(&mut self) {
+    self.intro = String::default();
+    self.outro = String::default();
 
-    let trimmed_content = trim::trim_start_regexp(self.content.as_str(), pat)?.to_owned();
-    self.content = trimmed_content.to_owned();
-    if !trimmed_content.is_empty() {
-      return Ok(());
+    if self.is_content_edited() {
+      self.content = self.original_str.to_owned();
+      self.name_index = None;
+      self.overwrite_start = None;
     }
-
-    let trimmed_outro = trim::trim_start_regexp(self.outro.as_str(), pat)?.to_owned();
-    self.outro = trimmed_outro;
-
-    Ok(())
   }
 
-  pub fn trim_end_regexp(&mut self, pat: &str) -> Result {
-    let trimmed_outro = trim::trim_end_regexp(self.outro.as_str(), pat)?.to_owned();
-    self.outro = trimmed_outro.to_owned();
-
-    if !trimmed_outro.is_empty() {
-      return Ok(());
-    }
-
-    let trimmed_content = trim::trim_end_regexp(self.content.as_str(), pat)?.to_owned();
-    self.content = trimmed_content.to_owned();
-    if !trimmed_content.is_empty() {
-      return Ok(());
-    }
+  // Length of the generated string of the chunk in bytes.
+  pub fn len(&self) -> usize {
+    self.intro.len() + self.content.len() + self.outro.len()
+  }
 
-    let trimmed_intro = trim::trim_end_regexp(self.intro.as_str(), pat)?.to_owned();
-    self.intro = trimmed_intro;
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
 
-    Ok(())
+  pub fn write_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+    w.write_str(self.intro.as_str())?;
+    w.write_str(self.content.as_str())?;
+    w.write_str(self.outro.as_str())
   }
 
   pub fn is_content_edited(&self) -> bool {
     self.original_str.len() != self.content.len() || self.original_str != self.content
   }
 
-  pub fn try_each_next<F>(chunk: Rc<RefCell<Chunk>>, mut f: F) -> Result
-  where
-    F: FnMut(Rc<RefCell<Chunk>>) -> Result<bool>,
-  {
-    let mut curr = Some(chunk);
-    while let Some(value) = curr {
-      match f(Rc::clone(&value)) {
-        Ok(should_yield) => {
-          if should_yield {
-            break;
-          }
-        }
-        Err(e) => {
-          return Err(e);
-        }
-      }
-      curr = value.borrow().next.as_ref().map(Rc::clone);
-    }
-    Ok(())
-  }
-
-  pub fn try_each_prev<F>(chunk: Rc<RefCell<Chunk>>, mut f: F) -> Result
-  where
-    F: FnMut(Rc<RefCell<Chunk>>) -> Result<bool>,
-  {
-    let mut curr = Some(chunk);
-    while let Some(value) = curr {
-      match f(Rc::clone(&value)) {
-        Ok(should_yield) => {
-          if should_yield {
-            break;
-          }
-        }
-        Err(e) => return Err(e),
-      }
-      curr = value.borrow().prev.as_ref().map(Rc::clone);
-    }
-    Ok(())
-  }
-
   pub fn contains(&self, index: u32) -> bool {
     index >= self.start && index < self.end
   }
 
-  pub fn split(chunk: Rc<RefCell<Chunk>>, index: u32) -> Rc<RefCell<Self>> {
-    let mut curr_chunk = chunk.borrow_mut();
+  // Splits the chunk at `index`, and returns the latter part. Linking the returned chunk is left to the caller.
+  pub fn split(&mut self, index: u32) -> Chunk {
+    let content_edited = self.is_content_edited();
 
-    let chunk_mid = (index - curr_chunk.start) as usize;
-    let chunk_str = curr_chunk.original_str[0..chunk_mid].to_owned();
-    let next_chunk_str = curr_chunk.original_str[chunk_mid..].to_owned();
+    let chunk_mid = (index - self.start) as usize;
+    let next_chunk_str = self.original_str.split_off(chunk_mid);
 
-    let next_chunk = Rc::new(RefCell::new(Chunk::new(
-      index,
-      curr_chunk.end,
-      next_chunk_str.as_str(),
-    )));
+    let mut next_chunk = Chunk::new(index, self.end, next_chunk_str.as_str());
 
     // `outro` of the current chunk will be moved to the newly created one and we need to reset the current one
-    next_chunk.borrow_mut().outro = curr_chunk.outro.to_owned();
-    curr_chunk.outro = String::default();
+    next_chunk.outro = std::mem::take(&mut self.outro);
 
-    if curr_chunk.is_content_edited() {
-      next_chunk.borrow_mut().content = String::default();
-      curr_chunk.content = String::default();
+    if content_edited {
+      next_chunk.overwrite_start = self.overwrite_start;
+      next_chunk.content = String::default();
+      self.content = String::default();
     } else {
-      curr_chunk.content = chunk_str.to_owned();
+      self.content = self.original_str.to_owned();
     }
 
-    curr_chunk.original_str = chunk_str;
-    curr_chunk.end = index;
+    self.end = index;
 
-    next_chunk.borrow_mut().next = {
-      if curr_chunk.next.is_some() {
-        Some(Rc::clone(curr_chunk.next.as_ref().unwrap()))
-      } else {
-        None
-      }
-    };
+    next_chunk
+  }
+}
 
-    curr_chunk.next = Some(Rc::clone(&next_chunk));
+impl fmt::Display for Chunk {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.write_to(f)
+  }
+}
 
-    next_chunk.borrow_mut().prev = Some(Rc::clone(&chunk));
+// Iterates over chunks in the order of the generated string by following `next` links.
+pub struct ChunkIter<'a> {
+  chunks: &'a [Chunk],
+  next: Option<ChunkId>,
+}
 
-    next_chunk
+impl<'a> ChunkIter<'a> {
+  pub fn new(chunks: &'a [Chunk], first: ChunkId) -> Self {
+    Self {
+      chunks,
+      next: Some(first),
+    }
   }
 }
 
-impl ToString for Chunk {
-  fn to_string(&self) -> String {
-    format!("{}{}{}", self.intro, self.content, self.outro)
+impl<'a> Iterator for ChunkIter<'a> {
+  type Item = (ChunkId, &'a Chunk);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let id = self.next?;
+    let chunk = &self.chunks[id.0];
+    self.next = chunk.next;
+
+    Some((id, chunk))
   }
 }
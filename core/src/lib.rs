@@ -9,9 +9,17 @@ mod utils;
 mod chunk;
 mod source_map;
 
+pub mod bundle;
 pub mod magic_string;
 pub mod result;
+pub mod source_map_consumer;
 
+pub use crate::bundle::*;
 pub use crate::magic_string::*;
+pub use crate::mapping::{Line, Mappings, Segment};
 pub use crate::result::*;
-pub use crate::source_map::SourceMap;
+pub use crate::source_map::{
+  decode_mappings, Section, SectionOffset, SectionedSourceMap, SourceMap, SourceMappingUrlStyle,
+};
+pub use crate::source_map_consumer::*;
+pub use regex::{Captures, Regex};
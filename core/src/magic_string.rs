@@ -1,19 +1,196 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc, string::ToString};
+use std::{
+  collections::{BTreeMap, HashMap, HashSet},
+  fmt,
+};
 
-use crate::utils::{normalize_index, trim};
+use crate::utils::{
+  indent::{guess_indent, indent_lines},
+  index::{char_boundary_error, IndexTranslator},
+  trim,
+};
 
 #[cfg(feature = "node-api")]
 use napi_derive::napi;
+use regex::{Regex, Replacer};
 
 use crate::{
-  chunk::Chunk,
-  mapping::{Mapping, Mappings},
+  chunk::{Chunk, ChunkId, ChunkIter},
+  mapping::{Mapping, Mappings, MappingsWriter},
   result::Result,
-  source_map::SourceMap,
+  source_map::{decode_mappings, SourceMap, SourceMappingUrlStyle},
   utils::locator::Locator,
   Error, MagicStringErrorType,
 };
 
+/// Unit in which columns of generated sourcemaps are counted.
+///
+/// The sourcemap specification, and every JavaScript consumer, counts columns in UTF-16 code units,
+/// which is the default. `Utf8` counts bytes and `Char` counts Unicode scalar values.
+/// On the JavaScript side, it is passed as one of the strings `"Utf16"`, `"Utf8"` or `"Char"`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnUnit {
+  #[default]
+  Utf16,
+  Utf8,
+  Char,
+}
+
+impl ColumnUnit {
+  pub(crate) fn len_of_str(&self, s: &str) -> u32 {
+    match self {
+      ColumnUnit::Utf16 => s.encode_utf16().count() as u32,
+      ColumnUnit::Utf8 => s.len() as u32,
+      ColumnUnit::Char => s.chars().count() as u32,
+    }
+  }
+
+  pub(crate) fn len_of_char(&self, c: char) -> u32 {
+    match self {
+      ColumnUnit::Utf16 => c.len_utf16() as u32,
+      ColumnUnit::Utf8 => c.len_utf8() as u32,
+      ColumnUnit::Char => 1,
+    }
+  }
+}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::TypeName for ColumnUnit {
+  fn type_name() -> &'static str {
+    "ColumnUnit"
+  }
+
+  fn value_type() -> napi::ValueType {
+    napi::ValueType::String
+  }
+}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::ValidateNapiValue for ColumnUnit {}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::FromNapiValue for ColumnUnit {
+  unsafe fn from_napi_value(
+    env: napi::sys::napi_env,
+    napi_val: napi::sys::napi_value,
+  ) -> napi::Result<Self> {
+    match String::from_napi_value(env, napi_val)?.as_str() {
+      "Utf16" => Ok(ColumnUnit::Utf16),
+      "Utf8" => Ok(ColumnUnit::Utf8),
+      "Char" => Ok(ColumnUnit::Char),
+      unit => Err(napi::Error::new(
+        napi::Status::InvalidArg,
+        format!("Invalid column unit: {}", unit),
+      )),
+    }
+  }
+}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::ToNapiValue for ColumnUnit {
+  unsafe fn to_napi_value(
+    env: napi::sys::napi_env,
+    val: Self,
+  ) -> napi::Result<napi::sys::napi_value> {
+    let unit = match val {
+      ColumnUnit::Utf16 => "Utf16",
+      ColumnUnit::Utf8 => "Utf8",
+      ColumnUnit::Char => "Char",
+    };
+    String::to_napi_value(env, unit.to_owned())
+  }
+}
+
+/// Unit in which indices of the original string, passed to methods of `MagicString`, are counted.
+///
+/// `Utf8` (the default) counts bytes. `Utf16` counts UTF-16 code units, which is how JavaScript strings are indexed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IndexUnit {
+  #[default]
+  Utf8,
+  Utf16,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MagicStringOptions {
+  pub index_unit: IndexUnit,
+}
+
+/// Resolution of the mappings generated for unedited content.
+///
+/// `Off` (the default) maps the start of each chunk and line, `Boundary` additionally maps the start of each word
+/// (a run of `[A-Za-z0-9_]`) and every other character, and `Full` maps every character.
+/// It can be created from a `bool` for backwards compatibility, where `true` means `Full`.
+/// On the JavaScript side, it is passed as `false`, `true` or `"boundary"`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Hires {
+  #[default]
+  Off,
+  Boundary,
+  Full,
+}
+
+impl From<bool> for Hires {
+  fn from(hires: bool) -> Self {
+    if hires {
+      Hires::Full
+    } else {
+      Hires::Off
+    }
+  }
+}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::TypeName for Hires {
+  fn type_name() -> &'static str {
+    "Hires"
+  }
+
+  fn value_type() -> napi::ValueType {
+    // either a boolean or a string
+    napi::ValueType::Unknown
+  }
+}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::ValidateNapiValue for Hires {}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::FromNapiValue for Hires {
+  unsafe fn from_napi_value(
+    env: napi::sys::napi_env,
+    napi_val: napi::sys::napi_value,
+  ) -> napi::Result<Self> {
+    let mut value_type = 0;
+    napi::check_status!(napi::sys::napi_typeof(env, napi_val, &mut value_type))?;
+
+    if napi::ValueType::from(value_type) == napi::ValueType::Boolean {
+      return Ok(bool::from_napi_value(env, napi_val)?.into());
+    }
+
+    match String::from_napi_value(env, napi_val)?.as_str() {
+      "boundary" => Ok(Hires::Boundary),
+      hires => Err(napi::Error::new(
+        napi::Status::InvalidArg,
+        format!("Invalid hires: {}", hires),
+      )),
+    }
+  }
+}
+
+#[cfg(feature = "node-api")]
+impl napi::bindgen_prelude::ToNapiValue for Hires {
+  unsafe fn to_napi_value(
+    env: napi::sys::napi_env,
+    val: Self,
+  ) -> napi::Result<napi::sys::napi_value> {
+    match val {
+      Hires::Off => bool::to_napi_value(env, false),
+      Hires::Boundary => String::to_napi_value(env, "boundary".to_owned()),
+      Hires::Full => bool::to_napi_value(env, true),
+    }
+  }
+}
+
 #[cfg(feature = "node-api")]
 #[napi(object)]
 #[derive(Debug, Default, Clone)]
@@ -22,7 +199,10 @@ pub struct GenerateDecodedMapOptions {
   pub source_root: Option<String>,
   pub source: Option<String>,
   pub include_content: bool,
-  pub hires: bool,
+  pub hires: Hires,
+  pub column_unit: ColumnUnit,
+  pub ignore_list: Vec<u32>,
+  pub debug_id: Option<String>,
 }
 
 #[cfg(not(feature = "node-api"))]
@@ -32,7 +212,10 @@ pub struct GenerateDecodedMapOptions {
   pub source_root: Option<String>,
   pub source: Option<String>,
   pub include_content: bool,
-  pub hires: bool,
+  pub hires: Hires,
+  pub column_unit: ColumnUnit,
+  pub ignore_list: Vec<u32>,
+  pub debug_id: Option<String>,
 }
 
 #[cfg(feature = "node-api")]
@@ -40,38 +223,172 @@ pub struct GenerateDecodedMapOptions {
 #[derive(Debug, Default, Clone)]
 pub struct OverwriteOptions {
   pub content_only: bool,
+  pub store_name: bool,
 }
 
 #[cfg(not(feature = "node-api"))]
 #[derive(Debug, Default, Clone)]
 pub struct OverwriteOptions {
   pub content_only: bool,
+  pub store_name: bool,
+}
+
+#[cfg(feature = "node-api")]
+#[napi(object)]
+#[derive(Debug, Default, Clone)]
+pub struct UpdateOptions {
+  pub overwrite: bool,
+  pub store_name: bool,
+}
+
+#[cfg(not(feature = "node-api"))]
+#[derive(Debug, Default, Clone)]
+pub struct UpdateOptions {
+  pub overwrite: bool,
+  pub store_name: bool,
+}
+
+#[cfg(feature = "node-api")]
+#[napi(object)]
+#[derive(Debug, Default, Clone)]
+pub struct IndentOptions {
+  pub exclude: Option<Vec<Vec<u32>>>,
+  pub indent_start: Option<bool>,
+}
+
+#[cfg(not(feature = "node-api"))]
+#[derive(Debug, Default, Clone)]
+pub struct IndentOptions {
+  pub exclude: Option<Vec<Vec<u32>>>,
+  pub indent_start: Option<bool>,
+}
+
+/// Patterns accepted by `trim_with`, `trim_start_with` and `trim_end_with`.
+/// A `Regex` is matched repeatedly at the start or the end of the generated string, with the flags it was built with, whereas a closure accepts the `char`s to be trimmed.
+pub trait TrimPattern {
+  fn trim_start_matches<'a>(&self, s: &'a str) -> &'a str;
+  fn trim_end_matches<'a>(&self, s: &'a str) -> &'a str;
+}
+
+impl TrimPattern for trim::TrimRegexp {
+  fn trim_start_matches<'a>(&self, s: &'a str) -> &'a str {
+    self.trim_start(s)
+  }
+
+  fn trim_end_matches<'a>(&self, s: &'a str) -> &'a str {
+    self.trim_end(s)
+  }
+}
+
+impl TrimPattern for Regex {
+  fn trim_start_matches<'a>(&self, s: &'a str) -> &'a str {
+    trim::trim_start_regexp(s, self)
+  }
+
+  fn trim_end_matches<'a>(&self, s: &'a str) -> &'a str {
+    trim::trim_end_regexp(s, self)
+  }
+}
+
+impl TrimPattern for &Regex {
+  fn trim_start_matches<'a>(&self, s: &'a str) -> &'a str {
+    trim::trim_start_regexp(s, self)
+  }
+
+  fn trim_end_matches<'a>(&self, s: &'a str) -> &'a str {
+    trim::trim_end_regexp(s, self)
+  }
 }
 
-#[derive(Debug, Serialize)]
+impl<F: Fn(char) -> bool> TrimPattern for F {
+  fn trim_start_matches<'a>(&self, s: &'a str) -> &'a str {
+    s.trim_start_matches(self)
+  }
+
+  fn trim_end_matches<'a>(&self, s: &'a str) -> &'a str {
+    s.trim_end_matches(self)
+  }
+}
+
+// Removes up to `len` bytes from the start of `s`, returns the number of bytes removed.
+fn drain_start(s: &mut String, len: usize) -> usize {
+  let len = len.min(s.len());
+  s.drain(..len);
+  len
+}
+
+// Removes up to `len` bytes from the end of `s`, returns the number of bytes removed.
+fn drain_end(s: &mut String, len: usize) -> usize {
+  let len = len.min(s.len());
+  s.truncate(s.len() - len);
+  len
+}
+
+/// Patterns accepted by `replace` and `replace_all`.
+/// A string is matched literally, whereas a `Regex` is used as is.
+pub trait ReplacePattern {
+  fn to_regex(&self) -> Result<Regex>;
+}
+
+impl ReplacePattern for &str {
+  fn to_regex(&self) -> Result<Regex> {
+    Ok(Regex::new(regex::escape(self).as_str())?)
+  }
+}
+
+impl ReplacePattern for Regex {
+  fn to_regex(&self) -> Result<Regex> {
+    Ok(self.clone())
+  }
+}
+
+impl ReplacePattern for &Regex {
+  fn to_regex(&self) -> Result<Regex> {
+    Ok((*self).clone())
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DecodedMap {
   pub file: Option<String>,
   pub sources: Vec<Option<String>>,
+  // snake_case names are what `DecodedMap` used to be serialized with
+  #[serde(alias = "source_root")]
   pub source_root: Option<String>,
+  #[serde(alias = "sources_content")]
   pub sources_content: Vec<Option<String>>,
   pub names: Vec<String>,
   pub mappings: Mappings,
+  #[serde(rename = "x_google_ignoreList")]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  #[serde(default)]
+  pub ignore_list: Vec<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  #[serde(default)]
+  pub debug_id: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct MagicString {
-  original_str: String,
+  pub(crate) original_str: String,
   original_str_locator: Locator,
+  index_translator: IndexTranslator,
 
   intro: String,
   outro: String,
 
-  chunk_by_start: HashMap<u32, Rc<RefCell<Chunk>>>,
-  chunk_by_end: HashMap<u32, Rc<RefCell<Chunk>>>,
+  sourcemap_locations: HashSet<u32>,
+  pub(crate) stored_names: Vec<String>,
 
-  last_searched_chunk: Rc<RefCell<Chunk>>,
-  first_chunk: Rc<RefCell<Chunk>>,
-  last_chunk: Rc<RefCell<Chunk>>,
+  // all chunks ever created, which are linked through `ChunkId`s in the order of the generated string
+  chunks: Vec<Chunk>,
+  // ordered by index, so the chunk containing an index can be found in logarithmic time
+  chunk_by_start: BTreeMap<u32, ChunkId>,
+  chunk_by_end: BTreeMap<u32, ChunkId>,
+
+  first_chunk: ChunkId,
+  last_chunk: ChunkId,
 }
 
 impl MagicString {
@@ -88,7 +405,27 @@ impl MagicString {
   ///
   ///
   pub fn new(str: &str) -> MagicString {
-    let original_chunk = Rc::new(RefCell::new(Chunk::new(0u32, str.len() as u32, str)));
+    MagicString::new_with_options(str, MagicStringOptions::default())
+  }
+
+  /// ## Create a new `MagicString` instance with options
+  ///
+  /// `index_unit` of `MagicStringOptions` sets the unit in which indices of the original string are counted (defaults to `IndexUnit::Utf8`, i.e. bytes).
+  /// Use `IndexUnit::Utf16` to index the string the way JavaScript does.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{IndexUnit, MagicString, MagicStringOptions, OverwriteOptions};
+  ///
+  /// let mut s = MagicString::new_with_options("const s = '中文'", MagicStringOptions {
+  ///   index_unit: IndexUnit::Utf16,
+  /// });
+  /// s.overwrite(11, 13, "English", OverwriteOptions::default());
+  ///
+  /// assert_eq!(s.to_string(), "const s = 'English'");
+  /// ```
+  pub fn new_with_options(str: &str, options: MagicStringOptions) -> MagicString {
+    let original_chunk = ChunkId(0);
 
     MagicString {
       original_str: String::from(str),
@@ -96,14 +433,18 @@ impl MagicString {
       intro: String::default(),
       outro: String::default(),
 
-      chunk_by_start: HashMap::default(),
-      chunk_by_end: HashMap::default(),
+      sourcemap_locations: HashSet::default(),
+      stored_names: Vec::default(),
+
+      chunks: vec![Chunk::new(0u32, str.len() as u32, str)],
+      chunk_by_start: BTreeMap::default(),
+      chunk_by_end: BTreeMap::default(),
 
-      first_chunk: Rc::clone(&original_chunk),
-      last_chunk: Rc::clone(&original_chunk),
-      last_searched_chunk: Rc::clone(&original_chunk),
+      first_chunk: original_chunk,
+      last_chunk: original_chunk,
 
       original_str_locator: Locator::new(str),
+      index_translator: IndexTranslator::new(str, options.index_unit),
     }
   }
 
@@ -123,7 +464,7 @@ impl MagicString {
   ///
   /// ```
   pub fn append(&mut self, str: &str) -> Result<&mut Self> {
-    self.outro = format!("{}{}", self.outro, str);
+    self.outro.push_str(str);
 
     Ok(self)
   }
@@ -144,7 +485,7 @@ impl MagicString {
   ///
   /// ```
   pub fn prepend(&mut self, str: &str) -> Result<&mut Self> {
-    self.intro = format!("{}{}", str, self.intro);
+    self.intro.insert_str(0, str);
 
     Ok(self)
   }
@@ -153,12 +494,13 @@ impl MagicString {
   ///
   /// Same as `s.append_left(...)`, except that the inserted content will go before any previous appends or prepends at index. Returns `self`.
   pub fn prepend_left(&mut self, index: u32, str: &str) -> Result<&mut Self> {
+    let index = self.index_translator.to_byte_index(index as usize)?;
     self._split_at_index(index)?;
 
-    if let Some(chunk) = self.chunk_by_end.get(&index) {
-      chunk.borrow_mut().prepend_outro(str);
+    if let Some(&chunk) = self.chunk_by_end.get(&index) {
+      self.chunks[chunk].prepend_outro(str);
     } else {
-      self.intro = format!("{}{}", str, self.intro)
+      self.intro.insert_str(0, str);
     };
 
     Ok(self)
@@ -168,12 +510,13 @@ impl MagicString {
   ///
   /// Same as `s.append_right(...)`, except that the inserted content will go before any previous appends or prepends at index. Returns `self`.
   pub fn prepend_right(&mut self, index: u32, str: &str) -> Result<&mut Self> {
+    let index = self.index_translator.to_byte_index(index as usize)?;
     self._split_at_index(index)?;
 
-    if let Some(chunk) = self.chunk_by_start.get(&index) {
-      chunk.borrow_mut().prepend_intro(str);
+    if let Some(&chunk) = self.chunk_by_start.get(&index) {
+      self.chunks[chunk].prepend_intro(str);
     } else {
-      self.outro = format!("{}{}", str, self.outro)
+      self.outro.insert_str(0, str);
     };
 
     Ok(self)
@@ -184,12 +527,13 @@ impl MagicString {
   /// Appends the specified content at the index in the original string.
   /// If a range ending with index is subsequently moved, the insert will be moved with it. Returns this. See also `s.prepend_left(...)`. Returns `self`.
   pub fn append_left(&mut self, index: u32, str: &str) -> Result<&mut Self> {
+    let index = self.index_translator.to_byte_index(index as usize)?;
     self._split_at_index(index)?;
 
-    if let Some(chunk) = self.chunk_by_end.get(&index) {
-      chunk.borrow_mut().append_outro(str);
+    if let Some(&chunk) = self.chunk_by_end.get(&index) {
+      self.chunks[chunk].append_outro(str);
     } else {
-      self.intro = format!("{}{}", self.intro, str);
+      self.intro.push_str(str);
     };
 
     Ok(self)
@@ -200,10 +544,11 @@ impl MagicString {
   /// Appends the specified content at the index in the original string.
   /// If a range starting with index is subsequently moved, the insert will be moved with it. Returns this. See also `s.prepend_right(...)`. Returns `self`.
   pub fn append_right(&mut self, index: u32, str: &str) -> Result<&mut Self> {
+    let index = self.index_translator.to_byte_index(index as usize)?;
     self._split_at_index(index)?;
 
-    if let Some(chunk) = self.chunk_by_start.get(&index) {
-      chunk.borrow_mut().append_intro(str);
+    if let Some(&chunk) = self.chunk_by_start.get(&index) {
+      self.chunks[chunk].append_intro(str);
     } else {
       self.append(str)?;
     };
@@ -216,6 +561,7 @@ impl MagicString {
   /// Replaces the characters from start to end with content. Returns `self`.
   /// The fourth argument is optional.
   /// - and a `content_only` property which determines whether only the content is overwritten, or anything that was appended/prepended to the range as well.
+  /// - and a `store_name` property which, if `true`, stores the original content in the `names` field of the generated sourcemap, so that the overwritten identifiers can be restored by sourcemap consumers.
   ///
   /// Example:
   /// ```
@@ -236,86 +582,173 @@ impl MagicString {
     content: &str,
     options: OverwriteOptions,
   ) -> Result<&mut Self> {
-    let content_only = options.content_only;
-    let start = normalize_index(self.original_str.as_str(), start)?;
-    let end = normalize_index(self.original_str.as_str(), end)?;
+    let start = self.index_translator.normalize(start)?;
+    let end = self.index_translator.normalize(end)?;
+
+    self._update(
+      start,
+      end,
+      content,
+      options.content_only,
+      options.store_name,
+    )
+  }
+
+  /// ## Update
+  ///
+  /// Replaces the characters from start to end with content. Returns `self`.
+  /// Unlike `overwrite`, content appended/prepended to the range is kept by default.
+  /// The fourth argument is optional.
+  /// - and an `overwrite` property which, if `true`, clears anything that was appended/prepended to the range as well.
+  /// - and a `store_name` property which, if `true`, stores the original content in the `names` field of the generated sourcemap.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, UpdateOptions};
+  ///
+  /// let mut s = MagicString::new("abcdefg");
+  ///
+  /// s.append_left(4, "(");
+  /// s.update(1, 4, "z", UpdateOptions::default());
+  /// assert_eq!(s.to_string(), "az(efg")
+  /// ```
+  pub fn update(
+    &mut self,
+    start: i64,
+    end: i64,
+    content: &str,
+    options: UpdateOptions,
+  ) -> Result<&mut Self> {
+    let start = self.index_translator.normalize(start)?;
+    let end = self.index_translator.normalize(end)?;
+
+    self._update(start, end, content, !options.overwrite, options.store_name)
+  }
 
-    let start = start as u32;
-    let end = end as u32;
+  /// ## Reset
+  ///
+  /// Resets the modified characters from start to end to their original content, anything that was appended/prepended to the range is removed as well. Returns `self`.
+  /// Resetting a part of an edited range will cause an error.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, OverwriteOptions};
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  ///
+  /// s.overwrite(1, 4, "z", OverwriteOptions::default());
+  /// s.remove(6, 9);
+  /// assert_eq!(s.to_string(), "azefjkl");
+  ///
+  /// s.reset(0, 12);
+  /// assert_eq!(s.to_string(), "abcdefghijkl");
+  /// ```
+  pub fn reset(&mut self, start: i64, end: i64) -> Result<&mut Self> {
+    let start = self.index_translator.normalize(start)?;
+    let end = self.index_translator.normalize(end)?;
 
     if start == end {
-      return Err(Error::new_with_reason(
-        MagicStringErrorType::MagicStringOutOfRangeError,
-        "Start and end should not be the same. Please consider using `append_(left|right)` or `prepend_(left|right)` instead",
-      ));
+      return Ok(self);
     }
 
     if start > end {
       return Err(Error::new_with_reason(
         MagicStringErrorType::MagicStringOutOfRangeError,
-        "Start must be greater than end.",
+        "Start must be less than end.",
       ));
     }
 
     self._split_at_index(start)?;
     self._split_at_index(end)?;
 
-    let start_chunk: Option<Rc<RefCell<Chunk>>> = self.chunk_by_start.get(&start).map(Rc::clone);
-    let end_chunk: Option<Rc<RefCell<Chunk>>> = self.chunk_by_end.get(&end).map(Rc::clone);
-
-    if let Some(start_chunk) = start_chunk {
-      // Note: This original implementation looks a little bit weird to me.
-      // It should check whether the latter chunks had been edited(not only for content-wise, but also for intro and outro) or not,
-      // then we could return the Error. But for now, It's been doing just fine.
-      if start_chunk.borrow().end < end
-        && (start_chunk.borrow().next
-          != self
-            .chunk_by_start
-            .get(&start_chunk.borrow().end)
-            .map(Rc::clone))
-      {
-        return Err(Error::new_with_reason(
-          MagicStringErrorType::MagicStringCrossChunkError,
-          "unable to edit overlapped chunks",
-        ));
-      }
-
-      Chunk::try_each_next(Rc::clone(&start_chunk), |chunk| {
-        if start_chunk == chunk {
-          start_chunk.borrow_mut().content = content.to_owned();
-          if !content_only {
-            start_chunk.borrow_mut().intro = String::default();
-            start_chunk.borrow_mut().outro = String::default();
-          }
-
-          return Ok(false);
-        }
+    let mut curr = self.chunk_by_start.get(&start).copied();
 
-        if end_chunk.is_some()
-          && chunk.borrow().start
-            >= (end_chunk.as_ref().map(Rc::clone).unwrap() as Rc<RefCell<Chunk>>)
-              .borrow()
-              .end
-        {
-          return Ok(true);
-        }
+    if let Some(start_chunk) = curr {
+      self._check_cross_chunk(start_chunk, end)?;
+    }
 
-        chunk.borrow_mut().content = String::default();
-        if !content_only {
-          chunk.borrow_mut().intro = String::default();
-          chunk.borrow_mut().outro = String::default();
-        }
+    // walk through the chunks in the order of the original string, as they may have been moved
+    while let Some(id) = curr {
+      let chunk = &mut self.chunks[id];
+      chunk.reset();
 
-        Ok(false)
-      })?
+      curr = if end > chunk.end {
+        self.chunk_by_start.get(&chunk.end).copied()
+      } else {
+        None
+      };
     }
 
     Ok(self)
   }
 
+  /// ## Replace
+  ///
+  /// Replaces the first match of `pattern` in the original string with `replacement`, which is done by `overwrite`, so that sourcemaps are kept correct. Returns `self`.
+  /// If any match can't be overwritten, e.g. it overlaps edited content, an error is returned and no match is replaced.
+  /// `pattern` can either be a string, which is matched literally, or a `Regex`.
+  /// `replacement` can either be a string, in which `$1` or `${name}` will be substituted with the corresponding capture group (use `$$` for a literal `$`), or a closure which receives the `Captures` of the match.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{Captures, MagicString, Regex};
+  ///
+  /// let mut s = MagicString::new("if (process.env.NODE_ENV === 'development') {}");
+  /// s.replace("process.env.NODE_ENV", "'production'");
+  ///
+  /// assert_eq!(s.to_string(), "if ('production' === 'development') {}");
+  ///
+  /// let mut s = MagicString::new("let a = 1, b = 2");
+  /// s.replace(Regex::new(r"(\w) = (\d)").unwrap(), "$1 = ${2}0");
+  ///
+  /// assert_eq!(s.to_string(), "let a = 10, b = 2");
+  ///
+  /// let mut s = MagicString::new("let a = 1, b = 2");
+  /// s.replace(Regex::new(r"\d").unwrap(), |caps: &Captures| {
+  ///   (caps[0].parse::<u32>().unwrap() * 2).to_string()
+  /// });
+  ///
+  /// assert_eq!(s.to_string(), "let a = 2, b = 2");
+  /// ```
+  pub fn replace<P: ReplacePattern, R: Replacer>(
+    &mut self,
+    pattern: P,
+    replacement: R,
+  ) -> Result<&mut Self> {
+    self._replace(pattern, replacement, 1)
+  }
+
+  /// ## Replace all
+  ///
+  /// Same as `s.replace(...)`, except that all matches of `pattern` are replaced. Returns `self`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, Regex};
+  ///
+  /// let mut s = MagicString::new("a + a + b");
+  /// s.replace_all("a", "c");
+  ///
+  /// assert_eq!(s.to_string(), "c + c + b");
+  ///
+  /// let mut s = MagicString::new("foo-bar baz-qux");
+  /// s.replace_all(Regex::new(r"(\w+)-(\w+)").unwrap(), "$2-$1");
+  ///
+  /// assert_eq!(s.to_string(), "bar-foo qux-baz");
+  /// ```
+  pub fn replace_all<P: ReplacePattern, R: Replacer>(
+    &mut self,
+    pattern: P,
+    replacement: R,
+  ) -> Result<&mut Self> {
+    self._replace(pattern, replacement, 0)
+  }
+
   /// ## Trim start and end
   ///
   /// Trims content matching `pattern` (defaults to '\s', i.e. whitespace) from the start and the end. Returns `self`.
+  /// `pattern` is a regular expression, which is compiled once and matched repeatedly at the start and the end of the whole generated string,
+  /// so that it can match across edits, and multi-character patterns like `"\r\n"` trim every occurrence. See `trim_with` to pass a precompiled `Regex` or a `char` predicate instead.
   /// Note that in Rust, '\t'(char) and "\\t"(string) are different types, whereas they are regarded the same pattern in Regex, which means you can pass eiter one of them to `pattern` argument.
   ///
   /// Example:
@@ -336,9 +769,18 @@ impl MagicString {
   /// s.trim(Some("\t|\\s"));
   ///
   /// assert_eq!(s.to_string(), "abc");
+  ///
+  /// let mut s = MagicString::new("abc;\n ;");
+  /// s.prepend(";");
+  /// s.trim(Some("\\s*;"));
+  ///
+  /// assert_eq!(s.to_string(), "abc");
   /// ```
   pub fn trim(&mut self, pattern: Option<&str>) -> Result<&mut Self> {
-    self.trim_start(pattern)?.trim_end(pattern)
+    match pattern {
+      Some(pattern) => self.trim_with(trim::TrimRegexp::new(pattern, true, true)?),
+      None => self.trim_with(char::is_whitespace),
+    }
   }
 
   /// ## Trim start
@@ -367,36 +809,10 @@ impl MagicString {
   /// assert_eq!(s.to_string(), "a  abc");
   /// ```
   pub fn trim_start(&mut self, pattern: Option<&str>) -> Result<&mut Self> {
-    let pattern = pattern.unwrap_or("\\s");
-
-    self.intro = trim::trim_start_regexp(self.intro.as_str(), pattern)?.to_owned();
-
-    if !self.intro.is_empty() {
-      return Ok(self);
-    }
-
-    let error = Error::default();
-
-    Chunk::try_each_next(Rc::clone(&self.first_chunk), |chunk| {
-      self.last_searched_chunk = Rc::clone(&chunk);
-      if let Err(e) = chunk.borrow_mut().trim_start_regexp(pattern) {
-        return Err(e);
-      }
-
-      Ok(!chunk.borrow().to_string().is_empty())
-    })?;
-
-    if error != Error::default() {
-      return Err(error);
-    }
-
-    if self.last_searched_chunk == self.last_chunk
-      && self.last_chunk.borrow().content.to_string().is_empty()
-    {
-      self.outro = trim::trim_start_regexp(self.outro.as_str(), pattern)?.to_owned()
+    match pattern {
+      Some(pattern) => self.trim_start_with(trim::TrimRegexp::new(pattern, true, false)?),
+      None => self.trim_start_with(char::is_whitespace),
     }
-
-    Ok(self)
   }
 
   /// ## Trim end
@@ -424,28 +840,53 @@ impl MagicString {
   ///
   /// assert_eq!(s.to_string(), "abc  a");
   pub fn trim_end(&mut self, pattern: Option<&str>) -> Result<&mut Self> {
-    let pattern = pattern.unwrap_or("\\s");
+    match pattern {
+      Some(pattern) => self.trim_end_with(trim::TrimRegexp::new(pattern, false, true)?),
+      None => self.trim_end_with(char::is_whitespace),
+    }
+  }
 
-    self.outro = trim::trim_end_regexp(self.outro.as_str(), pattern)?.to_owned();
+  /// ## Trim start and end with
+  ///
+  /// Same as `trim`, except that `pattern` can either be a precompiled `Regex`, or a closure which accepts the `char`s to be trimmed. Returns `self`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, Regex};
+  ///
+  /// let pattern = Regex::new("\r\n").unwrap();
+  ///
+  /// let mut s = MagicString::new("\r\n\r\nabc\r\n");
+  /// s.trim_with(&pattern);
+  ///
+  /// assert_eq!(s.to_string(), "abc");
+  ///
+  /// let mut s = MagicString::new("——abc——");
+  /// s.trim_with(|c: char| c == '—');
+  ///
+  /// assert_eq!(s.to_string(), "abc");
+  /// ```
+  pub fn trim_with<P: TrimPattern>(&mut self, pattern: P) -> Result<&mut Self> {
+    self._trim_start(&pattern);
+    self._trim_end(&pattern);
 
-    if !self.outro.is_empty() {
-      return Ok(self);
-    }
+    Ok(self)
+  }
 
-    Chunk::try_each_prev(Rc::clone(&self.last_chunk), |chunk| {
-      self.last_searched_chunk = Rc::clone(&chunk);
-      if let Err(e) = chunk.borrow_mut().trim_end_regexp(pattern) {
-        return Err(e);
-      }
+  /// ## Trim start with
+  ///
+  /// Same as `trim_start`, except that `pattern` can either be a precompiled `Regex`, or a `char` predicate. Returns `self`.
+  pub fn trim_start_with<P: TrimPattern>(&mut self, pattern: P) -> Result<&mut Self> {
+    self._trim_start(&pattern);
 
-      Ok(!chunk.borrow().to_string().is_empty())
-    })?;
+    Ok(self)
+  }
 
-    if self.last_searched_chunk == self.first_chunk
-      && self.first_chunk.borrow().content.to_string().is_empty()
-    {
-      self.intro = trim::trim_end_regexp(self.intro.as_str(), pattern)?.to_owned()
-    }
+  /// ## Trim end with
+  ///
+  /// Same as `trim_end`, except that `pattern` can either be a precompiled `Regex`, or a `char` predicate. Returns `self`.
+  pub fn trim_end_with<P: TrimPattern>(&mut self, pattern: P) -> Result<&mut Self> {
+    self._trim_end(&pattern);
 
     Ok(self)
   }
@@ -467,7 +908,7 @@ impl MagicString {
   /// assert_eq!(s.to_string(), "abc")
   /// ```
   pub fn trim_lines(&mut self) -> Result<&mut Self> {
-    self.trim_start(Some("\n"))?.trim_end(Some("\n"))
+    self.trim_with(|c: char| c == '\n')
   }
 
   /// ## Remove
@@ -488,12 +929,13 @@ impl MagicString {
   ///
   /// ```
   pub fn remove(&mut self, start: i64, end: i64) -> Result<&mut Self> {
-    let start = normalize_index(self.original_str.as_str(), start)?;
-    let end = normalize_index(self.original_str.as_str(), end)?;
+    let start = self.index_translator.normalize(start)?;
+    let end = self.index_translator.normalize(end)?;
 
-    let start = start as u32;
-    let end = end as u32;
+    self._remove(start, end)
+  }
 
+  fn _remove(&mut self, start: u32, end: u32) -> Result<&mut Self> {
     if start == end {
       // according to the original implementation, this is a noop.
       return Ok(self);
@@ -509,31 +951,319 @@ impl MagicString {
     self._split_at_index(start)?;
     self._split_at_index(end)?;
 
-    let start_chunk = self.chunk_by_start.get(&start);
-    let end_chunk = self.chunk_by_end.get(&end);
+    let end_chunk = self.chunk_by_end.get(&end).copied();
 
-    if start_chunk.is_some() {
-      Chunk::try_each_next(start_chunk.map(Rc::clone).unwrap(), |chunk| {
-        chunk.borrow_mut().content = String::default();
-        chunk.borrow_mut().intro = String::default();
-        chunk.borrow_mut().outro = String::default();
+    let mut curr = self.chunk_by_start.get(&start).copied();
+    while let Some(id) = curr {
+      let chunk = &mut self.chunks[id];
+      chunk.content = String::default();
+      chunk.intro = String::default();
+      chunk.outro = String::default();
+      chunk.overwrite_start = None;
+
+      if Some(id) == end_chunk {
+        break;
+      }
 
-        Ok(chunk == Rc::clone(end_chunk.unwrap()))
-      })?;
+      curr = chunk.next;
     }
 
     Ok(self)
   }
 
-  /// ## Is empty
+  /// ## Indent
   ///
-  /// Returns `true` if the resulting source is empty (disregarding white space).
+  /// Prefixes each line of the string with `indent_str`. If `indent_str` is `None`, the indentation will be guessed from the original string, falling back to a single tab character. Returns `self`.
+  /// The `options` argument can have an `exclude` property, which is an array of `[start, end]` character ranges. These ranges will be excluded from the indentation - useful for (e.g.) multiline strings.
+  /// An `indent_start` property of `Some(false)` means that the first line of the string will not be indented (defaults to `true`).
   ///
   /// Example:
   /// ```
-  /// use magic_string::MagicString;
+  /// use magic_string::{MagicString, IndentOptions};
   ///
-  /// let mut s = MagicString::new("");
+  /// let mut s = MagicString::new("abc\ndef\nghi");
+  /// s.indent(Some("  "), IndentOptions::default());
+  ///
+  /// assert_eq!(s.to_string(), "  abc\n  def\n  ghi");
+  ///
+  /// let mut s = MagicString::new("abc\ndef\nghi");
+  /// s.indent(Some("  "), IndentOptions {
+  ///   exclude: Some(vec![vec![4, 7]]),
+  ///   indent_start: Some(false),
+  /// });
+  ///
+  /// assert_eq!(s.to_string(), "abc\ndef\n  ghi");
+  /// ```
+  pub fn indent(&mut self, indent_str: Option<&str>, options: IndentOptions) -> Result<&mut Self> {
+    let indent_str = match indent_str {
+      Some(indent_str) => indent_str.to_owned(),
+      None => guess_indent(self.original_str.as_str()).unwrap_or_else(|| String::from("\t")),
+    };
+
+    if indent_str.is_empty() {
+      return Ok(self);
+    }
+
+    let mut exclusions: Vec<(u32, u32)> = vec![];
+    for range in options.exclude.unwrap_or_default() {
+      match range[..] {
+        [start, end] => exclusions.push((
+          self.index_translator.to_byte_index(start as usize)?,
+          self.index_translator.to_byte_index(end as usize)?,
+        )),
+        _ => {
+          return Err(Error::new_with_reason(
+            MagicStringErrorType::MagicStringOutOfRangeError,
+            "Exclusion ranges should be in the form of `[start, end]`.",
+          ))
+        }
+      }
+    }
+    let is_excluded = |index: u32| {
+      exclusions
+        .iter()
+        .any(|&(start, end)| index >= start && index < end)
+    };
+
+    let mut should_indent = options.indent_start.unwrap_or(true);
+
+    self.intro = indent_lines(self.intro.as_str(), indent_str.as_str(), &mut should_indent);
+
+    let mut curr = Some(self.first_chunk);
+    while let Some(mut id) = curr {
+      let chunk = &mut self.chunks[id];
+      let start = chunk.start;
+      let end = chunk.end;
+
+      if !is_excluded(start) {
+        chunk.intro = indent_lines(
+          chunk.intro.as_str(),
+          indent_str.as_str(),
+          &mut should_indent,
+        );
+      }
+
+      if chunk.is_content_edited() {
+        if !is_excluded(start) {
+          chunk.content = indent_lines(
+            chunk.content.as_str(),
+            indent_str.as_str(),
+            &mut should_indent,
+          );
+        }
+      } else {
+        let mut line_starts: Vec<u32> = vec![];
+
+        for (i, c) in self.original_str[start as usize..end as usize].char_indices() {
+          let index = start + i as u32;
+          if is_excluded(index) {
+            continue;
+          }
+
+          if c == '\n' {
+            should_indent = true;
+          } else if c != '\r' && should_indent {
+            should_indent = false;
+            line_starts.push(index);
+          }
+        }
+
+        // Indentations are inserted as intros, so that the content remains untouched and is still mapped to the original string.
+        for index in line_starts {
+          if index != self.chunks[id].start {
+            self._split_chunk_at_index(id, index)?;
+            id = self.chunks[id].next.unwrap();
+          }
+          self.chunks[id].append_intro(indent_str.as_str());
+        }
+      }
+
+      let chunk = &mut self.chunks[id];
+      if !is_excluded(end.saturating_sub(1)) {
+        chunk.outro = indent_lines(
+          chunk.outro.as_str(),
+          indent_str.as_str(),
+          &mut should_indent,
+        );
+      }
+
+      curr = chunk.next;
+    }
+
+    self.outro = indent_lines(self.outro.as_str(), indent_str.as_str(), &mut should_indent);
+
+    Ok(self)
+  }
+
+  /// ## Indent with
+  ///
+  /// Shorthand for `s.indent(Some(indent_str), IndentOptions::default())`. Returns `self`.
+  pub fn indent_with(&mut self, indent_str: &str) -> Result<&mut Self> {
+    self.indent(Some(indent_str), IndentOptions::default())
+  }
+
+  /// ## Slice
+  ///
+  /// Returns the content of the generated output that corresponds to the slice between `start` and `end` of the original string, including any content inserted inside the range.
+  /// Negative indices are counted from the end of the original string.
+  /// Returns an error if the indices are for characters that were already removed or overwritten, or if `start` is after `end` while the content between them was not moved.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, OverwriteOptions};
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  /// s.overwrite(3, 6, "DEF", OverwriteOptions::default());
+  /// s.append_left(9, "x");
+  ///
+  /// assert_eq!(s.slice(1, 10).unwrap(), "bcDEFghixj");
+  /// assert_eq!(s.slice(-3, -1).unwrap(), "jk");
+  /// assert!(s.slice(4, 8).is_err());
+  /// ```
+  pub fn slice(&self, start: i64, end: i64) -> Result<String> {
+    let start = self.index_translator.normalize(start)?;
+    let end = self.index_translator.normalize(end)?;
+
+    self._check_char_boundary(start)?;
+    self._check_char_boundary(end)?;
+
+    // `start` may be after `end` only if the content at `end` was moved after the content at `start`
+    let reversed_range_error = || {
+      Error::new_with_reason(
+        MagicStringErrorType::MagicStringOutOfRangeError,
+        "Start must be less than or equal to end.",
+      )
+    };
+
+    // nothing precedes index 0, so it can't be moved after `start`
+    if end == 0 && start > 0 {
+      return Err(reversed_range_error());
+    }
+
+    let mut result = String::default();
+
+    // find the chunk which contains `start`
+    let mut curr = Some(self.first_chunk);
+    while let Some(id) = curr {
+      let c = &self.chunks[id];
+      if c.start <= start && c.end > start {
+        break;
+      }
+
+      // the chunk containing `end` was moved before the one containing `start`
+      if c.start < end && c.end >= end {
+        if start > end {
+          return Err(reversed_range_error());
+        }
+        return Ok(result);
+      }
+
+      curr = c.next;
+    }
+
+    let start_chunk = match curr {
+      Some(id) => id,
+      None => return Ok(result),
+    };
+
+    if self.chunks[start_chunk].is_content_edited() && self.chunks[start_chunk].start != start {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::MagicStringReplacedAnchorError,
+        format!(
+          "Cannot use replaced character {} as slice start anchor.",
+          start
+        )
+        .as_str(),
+      ));
+    }
+
+    for (id, c) in ChunkIter::new(&self.chunks, start_chunk) {
+      let is_start_chunk = id == start_chunk;
+
+      if !is_start_chunk || c.start == start {
+        result.push_str(c.intro.as_str());
+      }
+
+      let contains_end = c.start < end && c.end >= end;
+      if contains_end && is_start_chunk && start > end {
+        return Err(reversed_range_error());
+      }
+
+      if contains_end && c.is_content_edited() && c.end != end {
+        return Err(Error::new_with_reason(
+          MagicStringErrorType::MagicStringReplacedAnchorError,
+          format!("Cannot use replaced character {} as slice end anchor.", end).as_str(),
+        ));
+      }
+
+      let slice_start = if is_start_chunk {
+        (start - c.start) as usize
+      } else {
+        0
+      };
+      let slice_end = if contains_end {
+        c.content.len() + end as usize - c.end as usize
+      } else {
+        c.content.len()
+      };
+      result.push_str(&c.content[slice_start..slice_end]);
+
+      if !contains_end || c.end == end {
+        result.push_str(c.outro.as_str());
+      }
+
+      if contains_end {
+        break;
+      }
+    }
+
+    Ok(result)
+  }
+
+  /// ## Snip
+  ///
+  /// Returns a new `MagicString` with the content outside `start` and `end` of the original string removed.
+  /// Edits made so far are kept, and indices of the returned instance are still relative to the original string, so do the generated sourcemaps.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, OverwriteOptions};
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  /// s.overwrite(6, 9, "GHI", OverwriteOptions::default());
+  ///
+  /// let snippet = s.snip(3, 9).unwrap();
+  ///
+  /// assert_eq!(snippet.to_string(), "defGHI");
+  /// assert_eq!(s.to_string(), "abcdefGHIjkl");
+  /// ```
+  pub fn snip(&self, start: i64, end: i64) -> Result<MagicString> {
+    let start = self.index_translator.normalize(start)?;
+    let end = self.index_translator.normalize(end)?;
+
+    if start > end {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::MagicStringOutOfRangeError,
+        "Start must be less than or equal to end.",
+      ));
+    }
+
+    let mut snippet = self.clone();
+    snippet._remove(0, start)?;
+    snippet._remove(end, self.original_str.len() as u32)?;
+
+    Ok(snippet)
+  }
+
+  /// ## Is empty
+  ///
+  /// Returns `true` if the resulting source is empty (disregarding white space).
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::MagicString;
+  ///
+  /// let mut s = MagicString::new("");
   ///
   /// assert_eq!(s.is_empty(), true);
   ///
@@ -542,14 +1272,256 @@ impl MagicString {
   /// assert_eq!(s.is_empty(), false);
   /// ```
   pub fn is_empty(&self) -> bool {
-    self.to_string().trim().is_empty()
+    self.intro.trim().is_empty()
+      && self._chunks().all(|(_, chunk)| {
+        chunk.intro.trim().is_empty()
+          && chunk.content.trim().is_empty()
+          && chunk.outro.trim().is_empty()
+      })
+      && self.outro.trim().is_empty()
   }
 
   /// ## Length
   ///
-  /// Returns the length of the modified string.
+  /// Returns the length of the modified string in bytes, without generating it.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::MagicString;
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  ///
+  /// s.remove(0, 3).unwrap();
+  /// s.prepend("// 😀\n").unwrap();
+  ///
+  /// assert_eq!(s.len(), 17);
+  /// assert_eq!(s.len(), s.to_string().len());
+  /// ```
   pub fn len(&self) -> usize {
-    self.to_string().len()
+    self.intro.len()
+      + self._chunks().map(|(_, chunk)| chunk.len()).sum::<usize>()
+      + self.outro.len()
+  }
+
+  /// ## Length in index unit
+  ///
+  /// Returns the length of the modified string counted in `index_unit` of `MagicStringOptions`, without generating it.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{IndexUnit, MagicString, MagicStringOptions};
+  ///
+  /// let mut s = MagicString::new_with_options(
+  ///   "中文",
+  ///   MagicStringOptions {
+  ///     index_unit: IndexUnit::Utf16,
+  ///     ..MagicStringOptions::default()
+  ///   },
+  /// );
+  /// s.append("😀").unwrap();
+  ///
+  /// assert_eq!(s.len_in_index_unit(), 4);
+  /// assert_eq!(s.len(), 10);
+  /// ```
+  pub fn len_in_index_unit(&self) -> usize {
+    let translator = &self.index_translator;
+
+    translator.len_of(self.intro.as_str())
+      + self
+        ._chunks()
+        .map(|(_, chunk)| {
+          translator.len_of(chunk.intro.as_str())
+            + translator.len_of(chunk.content.as_str())
+            + translator.len_of(chunk.outro.as_str())
+        })
+        .sum::<usize>()
+      + translator.len_of(self.outro.as_str())
+  }
+
+  /// ## Write to
+  ///
+  /// Writes the modified string to `w` piece by piece, without generating it as a whole.
+  /// To write to an `std::io::Write`, e.g. a file, use `write!(w, "{}", s)`, which streams the same way.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::MagicString;
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  /// s.overwrite(3, 6, "DEF", Default::default()).unwrap();
+  ///
+  /// let mut output = String::from("// output\n");
+  /// s.write_to(&mut output).unwrap();
+  ///
+  /// assert_eq!(output, "// output\nabcDEFghijkl");
+  /// ```
+  pub fn write_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+    w.write_str(self.intro.as_str())?;
+
+    for (_, chunk) in self._chunks() {
+      chunk.write_to(w)?;
+    }
+
+    w.write_str(self.outro.as_str())
+  }
+
+  /// ## To string
+  ///
+  /// Returns the modified string, which is written into a buffer sized by `len` up front.
+  /// The output is the same as the one of `Display`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::MagicString;
+  ///
+  /// let mut s = MagicString::new("abc");
+  /// s.append("def").unwrap();
+  ///
+  /// assert_eq!(s.to_string(), "abcdef");
+  /// assert_eq!(s.to_string(), format!("{}", s));
+  /// ```
+  // shadows `ToString` on purpose, as its blanket implementation grows the buffer along the way
+  #[allow(clippy::inherent_to_string_shadow_display)]
+  pub fn to_string(&self) -> String {
+    let mut str = String::with_capacity(self.len());
+    // writing into a `String` never fails
+    self.write_to(&mut str).unwrap();
+
+    str
+  }
+
+  /// ## Add sourcemap location
+  ///
+  /// Adds the specified character index (with respect to the original string) to sourcemap mappings, if `hires` is not `Full`.
+  /// Characters of unedited chunks will be mapped individually at these locations, which is useful for precise mappings without paying for `hires`. Returns `self`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions};
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  /// s.add_sourcemap_location(6);
+  ///
+  /// let map = s.generate_map(GenerateDecodedMapOptions::default()).unwrap();
+  ///
+  /// assert_eq!(map.mappings, "AAAA,MAAM");
+  /// ```
+  pub fn add_sourcemap_location(&mut self, index: u32) -> Result<&mut Self> {
+    let index = self.index_translator.to_byte_index(index as usize)?;
+    self._check_char_boundary(index)?;
+
+    self.sourcemap_locations.insert(index);
+
+    Ok(self)
+  }
+
+  /// ## Original index for
+  ///
+  /// Returns the index in the original string which the character at `generated_index` of the generated string comes from.
+  /// Characters of overwritten content are all mapped to the start of the overwritten range.
+  /// Returns `None` for inserted content (i.e. intros and outros), or if `generated_index` is out of range.
+  /// Both indices are counted in the `IndexUnit` of the instance.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, OverwriteOptions};
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  /// s.prepend("xyz");
+  /// s.overwrite(3, 6, "DEF!", OverwriteOptions::default());
+  ///
+  /// assert_eq!(s.to_string(), "xyzabcDEF!ghijkl");
+  /// assert_eq!(s.original_index_for(1), None);
+  /// assert_eq!(s.original_index_for(4), Some(1));
+  /// assert_eq!(s.original_index_for(9), Some(3));
+  /// assert_eq!(s.original_index_for(10), Some(6));
+  /// ```
+  pub fn original_index_for(&self, generated_index: usize) -> Option<u32> {
+    let translator = &self.index_translator;
+
+    let mut pos = translator.len_of(self.intro.as_str());
+    if generated_index < pos {
+      return None;
+    }
+
+    for (_, c) in self._chunks() {
+      pos += translator.len_of(c.intro.as_str());
+      if generated_index < pos {
+        return None;
+      }
+
+      let content_len = translator.len_of(c.content.as_str());
+      if generated_index < pos + content_len {
+        let start = translator.byte_to_index(c.start);
+        let original_index = if c.is_content_edited() {
+          start
+        } else {
+          start + generated_index - pos
+        };
+
+        return Some(original_index as u32);
+      }
+
+      pos += content_len + translator.len_of(c.outro.as_str());
+      if generated_index < pos {
+        return None;
+      }
+    }
+
+    None
+  }
+
+  /// ## Generated index for
+  ///
+  /// Returns the index in the generated string where the character at `original_index` of the original string ends up.
+  /// Characters of an overwritten range are all mapped to the start of the new content.
+  /// Returns `None` if the character was removed, or if `original_index` is out of range.
+  /// Both indices are counted in the `IndexUnit` of the instance.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, OverwriteOptions};
+  ///
+  /// let mut s = MagicString::new("abcdefghijkl");
+  /// s.prepend("xyz");
+  /// s.overwrite(3, 6, "DEF!", OverwriteOptions::default());
+  /// s.remove(9, 12);
+  ///
+  /// assert_eq!(s.to_string(), "xyzabcDEF!ghi");
+  /// assert_eq!(s.generated_index_for(1), Some(4));
+  /// assert_eq!(s.generated_index_for(4), Some(6));
+  /// assert_eq!(s.generated_index_for(6), Some(10));
+  /// assert_eq!(s.generated_index_for(10), None);
+  /// ```
+  pub fn generated_index_for(&self, original_index: u32) -> Option<usize> {
+    let translator = &self.index_translator;
+
+    let byte_index = translator.to_byte_index(original_index as usize).ok()?;
+    self._check_char_boundary(byte_index).ok()?;
+
+    let mut pos = translator.len_of(self.intro.as_str());
+
+    for (_, c) in self._chunks() {
+      pos += translator.len_of(c.intro.as_str());
+
+      if c.contains(byte_index) {
+        if c.content.is_empty() {
+          // a latter part of an overwritten range maps to the start of the new content
+          let head = self.chunk_by_start.get(&c.overwrite_start?)?;
+          return self._generated_index_of(*head);
+        }
+
+        if c.is_content_edited() {
+          return Some(pos);
+        }
+
+        return Some(pos + translator.len_of(&c.original_str[..(byte_index - c.start) as usize]));
+      }
+
+      pos += translator.len_of(c.content.as_str()) + translator.len_of(c.outro.as_str());
+    }
+
+    None
   }
 
   /// ## Generate decoded map
@@ -562,7 +1534,7 @@ impl MagicString {
   ///
   /// Example
   /// ```
-  /// use magic_string::{MagicString, GenerateDecodedMapOptions};
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions, Hires};
   ///
   /// let mut s = MagicString::new("export default React");
   /// s.prepend("import React from 'react'\n");
@@ -572,45 +1544,25 @@ impl MagicString {
   ///   source: Some("index.ts".to_owned()),
   ///   source_root: Some("./".to_owned()),
   ///   include_content: true,
-  ///   hires: false,
+  ///   hires: Hires::Off,
+  ///   ..GenerateDecodedMapOptions::default()
   /// });
   /// ```
   pub fn generate_decoded_map(&self, options: GenerateDecodedMapOptions) -> Result<DecodedMap> {
-    let mut map = Mapping::new(options.hires);
-    let locator = &self.original_str_locator;
+    let mut map = Mapping::new(options.hires, options.column_unit);
 
-    map.advance(self.intro.as_str());
-
-    Chunk::try_each_next(Rc::clone(&self.first_chunk), |chunk| {
-      let loc = locator.locate(chunk.borrow().start);
-      map.add_chunk(Rc::clone(&chunk), loc);
-      Ok(false)
-    })?;
-
-    map.advance(self.outro.as_str());
+    self.add_to_mapping(&mut map, 0, self.stored_names.as_slice())?;
 
-    Ok(DecodedMap {
-      file: options.file.to_owned(),
-      mappings: map.get_decoded_mappings(),
-      source_root: options.source_root.to_owned(),
-      sources: vec![options.source],
-      names: Vec::default(),
-      sources_content: {
-        if options.include_content {
-          vec![Some(self.original_str.to_owned())]
-        } else {
-          Default::default()
-        }
-      },
-    })
+    Ok(self._decoded_map(options, map.get_decoded_mappings()))
   }
 
   /// ## Generate Map
   ///
   /// Generates a version 3 sourcemap. All options are optional, see `GenerateDecodedMapOptions` for detailed document.
+  /// Mappings are encoded while walking through the chunks, without being decoded first.
   ///
   /// ```
-  /// use magic_string::{MagicString, GenerateDecodedMapOptions};
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions, Hires};
   ///
   /// let mut s = MagicString::new("export default React");
   /// s.prepend("import React from 'react'\n");
@@ -620,17 +1572,216 @@ impl MagicString {
   ///   source: Some("index.ts".to_owned()),
   ///   source_root: Some("./".to_owned()),
   ///   include_content: true,
-  ///   hires: true,
+  ///   hires: Hires::Full,
+  ///   ..GenerateDecodedMapOptions::default()
   /// }).expect("fail to generate map");
   ///
   /// generated_map.to_string(); // generates v3 sourcemap in JSON format
   /// generated_map.to_url(); // generates v3 inline sourcemap
   /// ```
   pub fn generate_map(&self, options: GenerateDecodedMapOptions) -> Result<SourceMap> {
-    let decoded_map = self.generate_decoded_map(options)?;
+    let mut map = Mapping::new_encoded(options.hires, options.column_unit);
+
+    self.add_to_mapping(&mut map, 0, self.stored_names.as_slice())?;
+
+    Ok(SourceMap::new_with_encoded_mappings(
+      map.into_encoded_mappings(),
+      self._decoded_map(options, Mappings::default()),
+    ))
+  }
+
+  // The decoded map of the string with the given mappings.
+  fn _decoded_map(&self, options: GenerateDecodedMapOptions, mappings: Mappings) -> DecodedMap {
+    DecodedMap {
+      file: options.file.to_owned(),
+      mappings,
+      source_root: options.source_root.to_owned(),
+      sources: vec![options.source],
+      names: self.stored_names.to_owned(),
+      sources_content: {
+        if options.include_content {
+          vec![Some(self.original_str.to_owned())]
+        } else {
+          Default::default()
+        }
+      },
+      ignore_list: options.ignore_list,
+      debug_id: options.debug_id,
+    }
+  }
+
+  /// ## Generate decoded map with input
+  ///
+  /// Same as `generate_decoded_map`, except that the mappings are traced through `input_map`, the sourcemap of the original string,
+  /// so that the generated sourcemap points to the original sources of `input_map`, like what `@ampproject/remapping` does.
+  /// `sources` and `sources_content` are taken from `input_map` (`source` of `GenerateDecodedMapOptions` is ignored), and only those which are referenced by the mappings are kept.
+  /// A traced segment takes the name from `input_map` if there's one, otherwise the stored name is used.
+  /// Segments which can't be traced, i.e. there's no mapping for them in `input_map`, are dropped.
+  /// Sources listed in `ignore_list` of `input_map` are still ignored, in addition to `ignore_list` of the options.
+  ///
+  /// Notice: Columns of `input_map` are expected to be counted in the same `column_unit`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions, Hires, OverwriteOptions};
+  ///
+  /// let mut ts = MagicString::new("let foo: number = 1;");
+  /// ts.remove(7, 15);
+  /// let input_map = ts.generate_map(GenerateDecodedMapOptions {
+  ///   source: Some("index.ts".to_owned()),
+  ///   hires: Hires::Full,
+  ///   ..GenerateDecodedMapOptions::default()
+  /// }).unwrap();
+  ///
+  /// let mut js = MagicString::new(ts.to_string().as_str());
+  /// js.overwrite(4, 7, "bar", OverwriteOptions::default());
+  ///
+  /// let map = js.generate_decoded_map_with_input(GenerateDecodedMapOptions::default(), &input_map).unwrap();
+  ///
+  /// assert_eq!(js.to_string(), "let bar = 1;");
+  /// assert_eq!(map.sources, vec![Some("index.ts".to_owned())]);
+  /// assert_eq!(map.mappings, vec![vec![vec![0, 0, 0, 0], vec![4, 0, 0, 4], vec![3, 0, 0, 11]]]);
+  /// ```
+  pub fn generate_decoded_map_with_input(
+    &self,
+    options: GenerateDecodedMapOptions,
+    input_map: &SourceMap,
+  ) -> Result<DecodedMap> {
+    let input_mappings =
+      Mapping::get_absolute_mappings(&decode_mappings(input_map.mappings.as_str())?);
+
+    let mut map = Mapping::new(options.hires, options.column_unit);
+
+    self.add_to_mapping(&mut map, 0, self.stored_names.as_slice())?;
+
+    let mut sources: Vec<Option<String>> = vec![];
+    let mut sources_content: Vec<Option<String>> = vec![];
+    let mut source_index_by_input_source_index: HashMap<i64, i64> = HashMap::default();
+    let mut names: Vec<String> = vec![];
+
+    map.remap(&input_mappings, |segment, input_segment| {
+      let source_index = *source_index_by_input_source_index
+        .entry(input_segment[1])
+        .or_insert_with(|| {
+          let input_source_index = input_segment[1] as usize;
+          sources.push(input_map.sources.get(input_source_index).cloned().flatten());
+          sources_content.push(
+            input_map
+              .sources_content
+              .get(input_source_index)
+              .cloned()
+              .flatten(),
+          );
+          (sources.len() - 1) as i64
+        });
+
+      let mut traced_segment = vec![segment[0], source_index, input_segment[2], input_segment[3]];
+
+      let name = match input_segment.get(4) {
+        Some(&name_index) => input_map.names.get(name_index as usize),
+        None => segment
+          .get(4)
+          .and_then(|&name_index| self.stored_names.get(name_index as usize)),
+      };
+      if let Some(name) = name {
+        let name_index = match names.iter().position(|n| n == name) {
+          Some(name_index) => name_index,
+          None => {
+            names.push(name.to_owned());
+            names.len() - 1
+          }
+        };
+        traced_segment.push(name_index as i64);
+      }
+
+      traced_segment
+    });
+
+    // sources ignored by the input map are still ignored
+    let mut ignore_list = input_map
+      .ignore_list
+      .iter()
+      .filter_map(|input_source_index| {
+        source_index_by_input_source_index
+          .get(&(*input_source_index as i64))
+          .map(|&source_index| source_index as u32)
+      })
+      .chain(options.ignore_list)
+      .collect::<Vec<_>>();
+    ignore_list.sort_unstable();
+    ignore_list.dedup();
+
+    Ok(DecodedMap {
+      file: options.file.to_owned(),
+      mappings: map.get_decoded_mappings(),
+      source_root: options
+        .source_root
+        .to_owned()
+        .or_else(|| input_map.source_root.to_owned()),
+      sources,
+      names,
+      sources_content: {
+        if options.include_content {
+          sources_content
+        } else {
+          Default::default()
+        }
+      },
+      ignore_list,
+      debug_id: options.debug_id,
+    })
+  }
+
+  /// ## Generate Map with input
+  ///
+  /// Generates a version 3 sourcemap whose mappings are traced through `input_map`. See `generate_decoded_map_with_input` for detailed document.
+  pub fn generate_map_with_input(
+    &self,
+    options: GenerateDecodedMapOptions,
+    input_map: &SourceMap,
+  ) -> Result<SourceMap> {
+    let decoded_map = self.generate_decoded_map_with_input(options, input_map)?;
     SourceMap::new_from_decoded(decoded_map)
   }
 
+  /// ## Finalize
+  ///
+  /// Generates a sourcemap, and returns the generated string with the comments referring to the sourcemap appended, along with the sourcemap itself.
+  /// A `//# sourceMappingURL=` comment (`/*# sourceMappingURL= */` for `CssComment`) is appended according to `url_style`,
+  /// preceded by a `//# debugId=` comment if `debug_id` is set. Writing the sourcemap for `External` is up to the caller.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions, SourceMappingUrlStyle};
+  ///
+  /// let mut s = MagicString::new("export default foo");
+  /// s.prepend("import foo from './foo'\n");
+  ///
+  /// let (code, map) = s.finalize(
+  ///   GenerateDecodedMapOptions {
+  ///     file: Some("index.js".to_owned()),
+  ///     source: Some("index.ts".to_owned()),
+  ///     ..GenerateDecodedMapOptions::default()
+  ///   },
+  ///   SourceMappingUrlStyle::External("index.js.map".to_owned()),
+  /// ).unwrap();
+  ///
+  /// assert_eq!(code, "import foo from './foo'\nexport default foo\n//# sourceMappingURL=index.js.map");
+  /// assert_eq!(map.mappings, ";AAAA");
+  /// ```
+  pub fn finalize(
+    &self,
+    options: GenerateDecodedMapOptions,
+    url_style: SourceMappingUrlStyle,
+  ) -> Result<(String, SourceMap)> {
+    let map = self.generate_map(options)?;
+    let mut code = self.to_string();
+
+    url_style.append_comments(&mut code, &map)?;
+
+    Ok((code, map))
+  }
+
   /// ## Move
   /// Moves the string between start and end to the specified position.Return `self`.
   ///
@@ -644,13 +1795,9 @@ impl MagicString {
   /// ```
   ///
   pub fn _move(&mut self, start: i64, end: i64, index: i64) -> Result<&mut Self> {
-    let start = normalize_index(self.original_str.as_str(), start)?;
-    let end = normalize_index(self.original_str.as_str(), end)?;
-    let index = normalize_index(self.original_str.as_str(), index)?;
-
-    let start = start as u32;
-    let end = end as u32;
-    let index = index as u32;
+    let start = self.index_translator.normalize(start)?;
+    let end = self.index_translator.normalize(end)?;
+    let index = self.index_translator.normalize(index)?;
 
     if index >= start && index <= end {
       return Err(Error::new_with_reason(
@@ -666,127 +1813,440 @@ impl MagicString {
       ));
     }
 
+    if start == end {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::MagicStringOutOfRangeError,
+        "Cannot move an empty range.",
+      ));
+    }
+
     self._split_at_index(start)?;
     self._split_at_index(end)?;
     self._split_at_index(index)?;
 
-    let first = self.chunk_by_start.get(&start).map(Rc::clone).unwrap();
-    let last = self.chunk_by_end.get(&end).map(Rc::clone).unwrap();
+    let out_of_range = || {
+      Error::new_with_reason(
+        MagicStringErrorType::MagicStringOutOfRangeError,
+        "Cannot move a range outside of the original string.",
+      )
+    };
 
-    let old_left = first.borrow().clone().prev;
-    let old_right = last.borrow().clone().next;
+    let first = *self.chunk_by_start.get(&start).ok_or_else(out_of_range)?;
+    let last = *self.chunk_by_end.get(&end).ok_or_else(out_of_range)?;
 
-    let new_right = self.chunk_by_start.get(&index).map(Rc::clone);
-    let new_left = match new_right.clone() {
-      Some(l) => Rc::clone(&l).borrow().clone().prev,
-      None => Some(Rc::clone(&self.last_chunk)),
-    };
-    let clone_old_left = old_left.clone();
+    let old_left = self.chunks[first].prev;
+    let old_right = self.chunks[last].next;
 
+    // unlink the chunks from `first` to `last`,
+    // both sides are missing only if the whole string is moved, which fails before anything is unlinked
     match old_left {
-      Some(old_left) => {
-        old_left.borrow_mut().next = old_right.clone();
-      }
-      None => self.first_chunk = old_right.clone().unwrap(),
+      Some(old_left) => self.chunks[old_left].next = old_right,
+      None => self.first_chunk = old_right.ok_or_else(out_of_range)?,
     }
 
     match old_right {
-      Some(old_right) => old_right.borrow_mut().prev = clone_old_left,
-      None => self.last_chunk = clone_old_left.unwrap(),
+      Some(old_right) => self.chunks[old_right].prev = old_left,
+      None => self.last_chunk = old_left.ok_or_else(out_of_range)?,
     }
 
+    // and link them between `new_left` and `new_right`, which are looked up after unlinking,
+    // as `last` may have been the last chunk (e.g. moving a range that is already in place)
+    let new_right = self.chunk_by_start.get(&index).copied();
+    let new_left = match new_right {
+      Some(new_right) => self.chunks[new_right].prev,
+      None => Some(self.last_chunk),
+    };
+
+    self.chunks[first].prev = new_left;
+    self.chunks[last].next = new_right;
+
     match new_left {
-      Some(new_left) => {
-        new_left.borrow_mut().next = Some(Rc::clone(&first));
-        first.borrow_mut().prev = Some(new_left);
-      }
-      None => {
-        let first = Rc::clone(&first);
-        self.first_chunk.borrow_mut().prev = Some(Rc::clone(&first));
-        last.borrow_mut().next = Some(Rc::clone(&self.first_chunk));
-        self.first_chunk = first;
-      }
+      Some(new_left) => self.chunks[new_left].next = Some(first),
+      None => self.first_chunk = first,
     }
 
     match new_right {
-      Some(new_right) => {
-        new_right.borrow_mut().prev = Some(Rc::clone(&last));
-        last.borrow_mut().next = Some(new_right);
+      Some(new_right) => self.chunks[new_right].prev = Some(last),
+      None => self.last_chunk = last,
+    }
+
+    Ok(self)
+  }
+
+  // `start` and `end` are byte offsets of the original string.
+  fn _update(
+    &mut self,
+    start: u32,
+    end: u32,
+    content: &str,
+    content_only: bool,
+    store_name: bool,
+  ) -> Result<&mut Self> {
+    self._prepare_update(start, end)?;
+
+    let start_chunk = self.chunk_by_start.get(&start).copied();
+    let end_chunk = self.chunk_by_end.get(&end).copied();
+
+    if let Some(start_chunk) = start_chunk {
+      // the name is recorded on the first chunk only, as the overwritten range may span several chunks
+      let name_index = match store_name {
+        true => {
+          let original = &self.original_str[start as usize..end as usize];
+          Some(
+            match self.stored_names.iter().position(|name| name == original) {
+              Some(name_index) => name_index,
+              None => {
+                self.stored_names.push(original.to_owned());
+                self.stored_names.len() - 1
+              }
+            },
+          )
+        }
+        false => None,
+      };
+
+      let chunk = &mut self.chunks[start_chunk];
+      chunk.content = content.to_owned();
+      chunk.name_index = name_index;
+      chunk.overwrite_start = None;
+      if !content_only {
+        chunk.intro = String::default();
+        chunk.outro = String::default();
+      }
+
+      let end_chunk_end = end_chunk.map(|end_chunk| self.chunks[end_chunk].end);
+
+      let mut curr = self.chunks[start_chunk].next;
+      while let Some(id) = curr {
+        let chunk = &mut self.chunks[id];
+        if matches!(end_chunk_end, Some(end_chunk_end) if chunk.start >= end_chunk_end) {
+          break;
+        }
+
+        chunk.content = String::default();
+        chunk.name_index = None;
+        chunk.overwrite_start = Some(start);
+        if !content_only {
+          chunk.intro = String::default();
+          chunk.outro = String::default();
+        }
+
+        curr = chunk.next;
       }
-      None => {
-        let last = Rc::clone(&last);
-        self.last_chunk.borrow_mut().next = Some(Rc::clone(&last));
-        first.borrow_mut().prev = Some(Rc::clone(&self.last_chunk));
-        last.borrow_mut().next = None;
-        self.last_chunk = last;
+    }
+
+    Ok(self)
+  }
+
+  // Validates the range of an update and splits chunks at its bounds, the content is left untouched.
+  fn _prepare_update(&mut self, start: u32, end: u32) -> Result {
+    if start == end {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::MagicStringOutOfRangeError,
+        "Start and end should not be the same. Please consider using `append_(left|right)` or `prepend_(left|right)` instead",
+      ));
+    }
+
+    if start > end {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::MagicStringOutOfRangeError,
+        "Start must be greater than end.",
+      ));
+    }
+
+    self._split_at_index(start)?;
+    self._split_at_index(end)?;
+
+    if let Some(&start_chunk) = self.chunk_by_start.get(&start) {
+      self._check_cross_chunk(start_chunk, end)?;
+    }
+
+    Ok(())
+  }
+
+  fn _check_cross_chunk(&self, start_chunk: ChunkId, end: u32) -> Result {
+    // Note: This original implementation looks a little bit weird to me.
+    // It should check whether the latter chunks had been edited(not only for content-wise, but also for intro and outro) or not,
+    // then we could return the Error. But for now, It's been doing just fine.
+    let start_chunk = &self.chunks[start_chunk];
+    if start_chunk.end < end
+      && start_chunk.next != self.chunk_by_start.get(&start_chunk.end).copied()
+    {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::MagicStringCrossChunkError,
+        "unable to edit overlapped chunks",
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Replaces at most `limit` matches of `pattern`, `0` means no limit.
+  fn _replace<P: ReplacePattern, R: Replacer>(
+    &mut self,
+    pattern: P,
+    mut replacement: R,
+    limit: usize,
+  ) -> Result<&mut Self> {
+    let regex = pattern.to_regex()?;
+
+    let mut replacements: Vec<(usize, usize, String)> = vec![];
+    for captures in regex.captures_iter(self.original_str.as_str()) {
+      if limit > 0 && replacements.len() >= limit {
+        break;
       }
+
+      let matched = captures.get(0).unwrap();
+      let mut content = String::default();
+      replacement.replace_append(&captures, &mut content);
+
+      replacements.push((matched.start(), matched.end(), content));
+    }
+
+    replacements.retain(|(start, end, content)| self.original_str[*start..*end] != *content);
+
+    // every range is checked before any of them is overwritten, so that a failing match leaves the content untouched
+    for (start, end, _) in replacements.iter() {
+      self._prepare_update(*start as u32, *end as u32)?;
+    }
+
+    for (start, end, content) in replacements {
+      self._update(start as u32, end as u32, content.as_str(), false, false)?;
     }
 
     Ok(self)
   }
 
+  // Adds mappings of the generated string to `map`, `names` is the `names` field of the sourcemap to be generated.
+  pub(crate) fn add_to_mapping<W: MappingsWriter>(
+    &self,
+    map: &mut Mapping<W>,
+    source_index: u32,
+    names: &[String],
+  ) -> Result {
+    let locator = &self.original_str_locator;
+
+    map.advance(self.intro.as_str());
+
+    for (_, chunk) in self._chunks() {
+      let loc = locator.locate(chunk.start, map.column_unit);
+      // `names` is either `stored_names` itself, or the merged names of a bundle
+      let name_index = chunk.name_index.and_then(|name_index| {
+        let name = self.stored_names.get(name_index)?;
+        match names.get(name_index) {
+          Some(n) if n == name => Some(name_index),
+          _ => names.iter().position(|n| n == name),
+        }
+      });
+      map.add_chunk(
+        chunk,
+        loc,
+        source_index,
+        name_index,
+        &self.sourcemap_locations,
+      );
+    }
+
+    map.advance(self.outro.as_str());
+
+    Ok(())
+  }
+
+  // Returns an error if `index` is out of range or falls inside a multibyte character of the original string.
+  fn _check_char_boundary(&self, index: u32) -> Result {
+    let index = index as usize;
+
+    if index > self.original_str.len() {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::MagicStringOutOfRangeError,
+        "index out of range",
+      ));
+    }
+
+    if self.original_str.is_char_boundary(index) {
+      return Ok(());
+    }
+
+    let (_, c) = self
+      .original_str
+      .char_indices()
+      .take_while(|&(i, _)| i < index)
+      .last()
+      .unwrap_or_default();
+
+    Err(char_boundary_error(index, c))
+  }
+
   fn _split_at_index(&mut self, index: u32) -> Result {
     if self.chunk_by_end.contains_key(&index) || self.chunk_by_start.contains_key(&index) {
       // early bail-out if it's already split
       return Ok(());
     }
 
-    let chunk = Rc::clone(&self.last_searched_chunk);
+    self._check_char_boundary(index)?;
 
-    let search_forward = index > chunk.borrow().start;
+    // the chunk with the greatest start not after `index`, falling back to the original chunk which always starts at 0
+    let id = self
+      .chunk_by_start
+      .range(..=index)
+      .next_back()
+      .map_or(ChunkId(0), |(_, &id)| id);
 
-    let mut curr = Some(chunk);
-    while let Some(c) = curr {
-      if c.borrow().contains(index) {
-        self._split_chunk_at_index(c, index)?;
-        return Ok(());
-      } else {
-        curr = {
-          if search_forward {
-            self.chunk_by_start.get(&c.borrow().end).map(Rc::clone)
-          } else {
-            self.chunk_by_end.get(&c.borrow().start).map(Rc::clone)
-          }
-        }
-      }
+    if self.chunks[id].contains(index) {
+      return self._split_chunk_at_index(id, index);
     }
 
     Ok(())
   }
 
-  fn _split_chunk_at_index(&mut self, chunk: Rc<RefCell<Chunk>>, index: u32) -> Result {
+  fn _split_chunk_at_index(&mut self, id: ChunkId, index: u32) -> Result {
+    let new_id = ChunkId(self.chunks.len());
+    let chunk = &mut self.chunks[id];
+
     // Zero-length edited chunks can be split into different chunks, cause split chunks are the same.
-    if chunk.borrow().is_content_edited() && !chunk.borrow().content.is_empty() {
+    if chunk.is_content_edited() && !chunk.content.is_empty() {
       return Err(Error::new(
         MagicStringErrorType::MagicStringDoubleSplitError,
       ));
     }
-    let next_chunk = chunk.borrow().clone().next;
-    let new_chunk = Chunk::split(Rc::clone(&chunk), index);
 
-    if let Some(next_chunk) = next_chunk {
-      next_chunk.borrow_mut().prev = Some(Rc::clone(&new_chunk));
+    let mut new_chunk = chunk.split(index);
+
+    new_chunk.prev = Some(id);
+    new_chunk.next = chunk.next;
+    chunk.next = Some(new_id);
+
+    if let Some(next) = new_chunk.next {
+      self.chunks[next].prev = Some(new_id);
     }
 
-    let new_chunk_original = new_chunk.borrow();
-    self.chunk_by_end.insert(index, Rc::clone(&chunk));
+    self.chunk_by_end.insert(index, id);
+    self.chunk_by_start.insert(index, new_id);
+    self.chunk_by_end.insert(new_chunk.end, new_id);
+    self.chunks.push(new_chunk);
 
-    self.chunk_by_start.insert(index, Rc::clone(&new_chunk));
-    self
-      .chunk_by_end
-      .insert(new_chunk_original.end, Rc::clone(&new_chunk));
+    if self.last_chunk == id {
+      self.last_chunk = new_id;
+    }
+
+    Ok(())
+  }
+
+  // Trims the generated string with `pattern`, which is matched against the whole of it.
+  fn _trim_start<P: TrimPattern>(&mut self, pattern: &P) {
+    let output = self.to_string();
+    let mut len = output.len() - pattern.trim_start_matches(output.as_str()).len();
+
+    // remove `len` bytes from the pieces in the order of the generated string
+    len -= drain_start(&mut self.intro, len);
+
+    let mut curr = Some(self.first_chunk);
+    while let Some(id) = curr {
+      if len == 0 {
+        break;
+      }
 
-    if self.last_chunk == chunk {
-      self.last_chunk = Rc::clone(&new_chunk);
+      let chunk = &mut self.chunks[id];
+      len -= drain_start(&mut chunk.intro, len);
+      len -= drain_start(&mut chunk.content, len);
+      len -= drain_start(&mut chunk.outro, len);
+
+      curr = chunk.next;
     }
 
-    self.last_searched_chunk = Rc::clone(&chunk);
+    drain_start(&mut self.outro, len);
+  }
+
+  fn _trim_end<P: TrimPattern>(&mut self, pattern: &P) {
+    let output = self.to_string();
+    let mut len = output.len() - pattern.trim_end_matches(output.as_str()).len();
 
-    Ok(())
+    // remove `len` bytes from the pieces in the reversed order of the generated string
+    len -= drain_end(&mut self.outro, len);
+
+    let mut curr = Some(self.last_chunk);
+    while let Some(id) = curr {
+      if len == 0 {
+        break;
+      }
+
+      let chunk = &mut self.chunks[id];
+      len -= drain_end(&mut chunk.outro, len);
+      len -= drain_end(&mut chunk.content, len);
+      len -= drain_end(&mut chunk.intro, len);
+
+      curr = chunk.prev;
+    }
+
+    drain_end(&mut self.intro, len);
+  }
+
+  // Index of the content of chunk `id` in the generated string, in the `IndexUnit` of the instance.
+  fn _generated_index_of(&self, id: ChunkId) -> Option<usize> {
+    let translator = &self.index_translator;
+
+    let mut pos = translator.len_of(self.intro.as_str());
+
+    for (curr, c) in self._chunks() {
+      pos += translator.len_of(c.intro.as_str());
+
+      if curr == id {
+        return (!c.content.is_empty()).then_some(pos);
+      }
+
+      pos += translator.len_of(c.content.as_str()) + translator.len_of(c.outro.as_str());
+    }
+
+    None
+  }
+
+  // Chunks in the order of the generated string.
+  fn _chunks(&self) -> ChunkIter<'_> {
+    ChunkIter::new(&self.chunks, self.first_chunk)
+  }
+}
+
+impl Clone for MagicString {
+  /// ## Clone
+  ///
+  /// Returns an independent copy of the `MagicString`, edits made to either one of them will not affect the other.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::MagicString;
+  ///
+  /// let s = MagicString::new("abcdefghijkl");
+  /// let mut cloned = s.clone();
+  ///
+  /// cloned.remove(0, 3);
+  ///
+  /// assert_eq!(s.to_string(), "abcdefghijkl");
+  /// assert_eq!(cloned.to_string(), "defghijkl");
+  /// ```
+  fn clone(&self) -> Self {
+    MagicString {
+      original_str: self.original_str.to_owned(),
+      original_str_locator: self.original_str_locator.clone(),
+      index_translator: self.index_translator.clone(),
+
+      intro: self.intro.to_owned(),
+      outro: self.outro.to_owned(),
+
+      sourcemap_locations: self.sourcemap_locations.clone(),
+      stored_names: self.stored_names.clone(),
+
+      // chunks are linked through ids, so they can be copied as they are
+      chunks: self.chunks.clone(),
+      chunk_by_start: self.chunk_by_start.clone(),
+      chunk_by_end: self.chunk_by_end.clone(),
+
+      first_chunk: self.first_chunk,
+      last_chunk: self.last_chunk,
+    }
   }
 }
 
-impl ToString for MagicString {
+impl fmt::Display for MagicString {
   /// ## To string
   ///
   /// Returns a modified string.
@@ -799,15 +2259,7 @@ impl ToString for MagicString {
   ///
   /// assert_eq!(s.to_string(), "abc");
   /// ```
-  fn to_string(&self) -> String {
-    let mut str = self.intro.to_owned();
-
-    Chunk::try_each_next(Rc::clone(&self.first_chunk), |chunk| {
-      str = format!("{}{}", str, chunk.borrow().to_string());
-      Ok(false)
-    })
-    .unwrap();
-
-    format!("{}{}", str, self.outro)
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.write_to(f)
   }
 }
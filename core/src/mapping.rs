@@ -1,67 +1,205 @@
-use std::{cell::RefCell, rc::Rc};
+use std::collections::HashSet;
 
 use crate::chunk::Chunk;
+use crate::magic_string::{ColumnUnit, Hires};
 use crate::result::Result;
 
-static SOURCE_INDEX: u8 = 0;
-
 pub type Segment = Vec<i64>;
 pub type Line = Vec<Segment>;
 pub type Mappings = Vec<Line>;
 
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Appends `value` to `output` as a base64 VLQ.
+fn encode_vlq(value: i64, output: &mut String) {
+  // the sign is stored in the least significant bit
+  let mut vlq = (value.unsigned_abs() << 1) | (value < 0) as u64;
+
+  loop {
+    let mut digit = vlq & 0b11111;
+    vlq >>= 5;
+    if vlq > 0 {
+      // more digits to come
+      digit |= 0b100000;
+    }
+    output.push(BASE64_CHARS[digit as usize] as char);
+
+    if vlq == 0 {
+      break;
+    }
+  }
+}
+
+// Where the segments collected by `Mapping` go, segments are positioned absolutely.
+pub trait MappingsWriter {
+  fn line_count(&self) -> usize;
+  // starts a new generated line
+  fn push_line(&mut self);
+  // adds a segment to the last generated line
+  fn push_segment(&mut self, segment: &[i64]);
+}
+
+impl MappingsWriter for Mappings {
+  fn line_count(&self) -> usize {
+    self.len()
+  }
+
+  fn push_line(&mut self) {
+    self.push(Line::default());
+  }
+
+  fn push_segment(&mut self, segment: &[i64]) {
+    if let Some(line) = self.last_mut() {
+      line.push(segment.to_vec());
+    }
+  }
+}
+
+// Encodes segments into VLQ mappings as they come, without keeping them.
+#[derive(Debug, Default)]
+pub struct MappingsEncoder {
+  mappings: String,
+  line_count: usize,
+  line_is_empty: bool,
+
+  // the previous segment, as segments are encoded relatively
+  generated_column: i64,
+  source_index: i64,
+  original_line: i64,
+  original_column: i64,
+  name_index: i64,
+}
+
+impl MappingsEncoder {
+  pub fn into_mappings(self) -> String {
+    self.mappings
+  }
+}
+
+impl MappingsWriter for MappingsEncoder {
+  fn line_count(&self) -> usize {
+    self.line_count
+  }
+
+  fn push_line(&mut self) {
+    if self.line_count > 0 {
+      self.mappings.push(';');
+    }
+    self.line_count += 1;
+    self.line_is_empty = true;
+    // generated columns are relative to the start of each line
+    self.generated_column = 0;
+  }
+
+  fn push_segment(&mut self, segment: &[i64]) {
+    if !self.line_is_empty {
+      self.mappings.push(',');
+    }
+    self.line_is_empty = false;
+
+    encode_vlq(segment[0] - self.generated_column, &mut self.mappings);
+    self.generated_column = segment[0];
+
+    // a segment which isn't mapped to any source
+    if segment.len() < 4 {
+      return;
+    }
+
+    encode_vlq(segment[1] - self.source_index, &mut self.mappings);
+    encode_vlq(segment[2] - self.original_line, &mut self.mappings);
+    encode_vlq(segment[3] - self.original_column, &mut self.mappings);
+    self.source_index = segment[1];
+    self.original_line = segment[2];
+    self.original_column = segment[3];
+
+    if let Some(&name_index) = segment.get(4) {
+      encode_vlq(name_index - self.name_index, &mut self.mappings);
+      self.name_index = name_index;
+    }
+  }
+}
+
 #[derive(Debug)]
-pub struct Mapping {
+pub struct Mapping<W = Mappings> {
   generated_code_line: u32,
   generated_code_column: u32,
-  hires: bool,
+  hires: Hires,
+  pub(crate) column_unit: ColumnUnit,
 
-  absolute_mappings: Mappings,
+  output: W,
 }
 
 impl Mapping {
-  pub fn new(hires: bool) -> Self {
+  pub fn new(hires: Hires, column_unit: ColumnUnit) -> Self {
+    // all lines and columns are absolutely related
+    // , which is a middle-island for us to convert it to relative mapping later (sourcemap specification)
+    Self::with_output(hires, column_unit, Mappings::default())
+  }
+
+  // a mapping of the given absolute mappings, e.g. to encode mappings which are merged from multiple sourcemaps
+  pub fn from_absolute_mappings(absolute_mappings: Mappings) -> Self {
+    Self::with_output(Hires::Off, ColumnUnit::default(), absolute_mappings)
+  }
+}
+
+impl Mapping<MappingsEncoder> {
+  // a mapping which encodes segments on the fly, use it if decoded mappings are not needed
+  pub fn new_encoded(hires: Hires, column_unit: ColumnUnit) -> Self {
+    Self::with_output(hires, column_unit, MappingsEncoder::default())
+  }
+
+  pub fn into_encoded_mappings(self) -> String {
+    self.output.into_mappings()
+  }
+}
+
+impl<W: MappingsWriter> Mapping<W> {
+  fn with_output(hires: Hires, column_unit: ColumnUnit, output: W) -> Self {
     Self {
       hires,
+      column_unit,
 
       generated_code_line: 0,
       generated_code_column: 0,
-      // all lines and columns are absolutely related
-      // , which is a middle-island for us to convert it to relative mapping later (sourcemap specification)
-      absolute_mappings: vec![],
+      output,
     }
   }
 
+  fn add_segment(&mut self, segment: &[i64]) {
+    // lines without any segments in between are left empty
+    while self.output.line_count() <= self.generated_code_line as usize {
+      self.output.push_line();
+    }
+    self.output.push_segment(segment);
+  }
+
   pub fn add_chunk(
     &mut self,
-    chunk: Rc<RefCell<Chunk>>,
+    chunk: &Chunk,
     (original_line, original_column): (u32, u32),
+    source_index: u32,
+    name_index: Option<usize>,
+    sourcemap_locations: &HashSet<u32>,
   ) {
-    let chunk_content_edited = chunk.borrow().is_content_edited();
-    self.advance(chunk.borrow().intro.as_str());
+    self.advance(chunk.intro.as_str());
 
-    if chunk_content_edited {
-      let content_str = chunk.borrow().content.to_owned();
-      let content_lines = content_str.split('\n').collect::<Vec<_>>();
+    if chunk.is_content_edited() {
+      let content_lines = chunk.content.split('\n').collect::<Vec<_>>();
 
       // In some edge case where `content` contains a line-break, which can be created through `overwrite`,
       // we must regard the content as a multi-line string.
       for (index, &s) in content_lines.iter().enumerate() {
         if !s.is_empty() {
-          let segment: Vec<i64> = vec![
+          let segment = [
             self.generated_code_column.into(),
-            SOURCE_INDEX.into(),
+            source_index.into(),
             original_line.into(),
             original_column.into(),
+            name_index.map_or(0, |name_index| name_index as i64),
           ];
+          let len = if name_index.is_some() { 5 } else { 4 };
 
-          if let Some(line) = self
-            .absolute_mappings
-            .get_mut(self.generated_code_line as usize)
-          {
-            line.push(segment)
-          } else {
-            self.absolute_mappings.push(vec![segment])
-          }
+          self.add_segment(&segment[..len]);
         }
 
         if index != content_lines.len() - 1 {
@@ -71,32 +209,37 @@ impl Mapping {
         } else {
           // We are currently at the last piece, this is the next starting piece.
           // So we have to set the next starting column for later use.
-          self.generated_code_column += s.len() as u32;
+          self.generated_code_column += self.column_unit.len_of_str(s);
         }
       }
     } else {
       let mut original_line = original_line as i64;
       let mut original_column = original_column as i64;
 
-      let original_str = chunk.borrow().original_str.to_owned();
+      let chunk_start = chunk.start;
       let mut first = true;
+      let mut in_word = false;
+
+      for (i, char) in chunk.original_str.char_indices() {
+        let at_boundary = match self.hires {
+          Hires::Off => false,
+          Hires::Boundary => {
+            // `\w` in JavaScript only matches ASCII characters
+            let is_word = char.is_ascii_alphanumeric() || char == '_';
+            let at_boundary = !(is_word && in_word);
+            in_word = is_word;
+            at_boundary
+          }
+          Hires::Full => true,
+        };
 
-      for char in original_str.chars() {
-        if self.hires || first {
-          let segment: Segment = vec![
+        if at_boundary || first || sourcemap_locations.contains(&(chunk_start + i as u32)) {
+          self.add_segment(&[
             self.generated_code_column.into(),
-            SOURCE_INDEX.into(),
+            source_index.into(),
             original_line,
             original_column,
-          ];
-          if let Some(line) = self
-            .absolute_mappings
-            .get_mut(self.generated_code_line as usize)
-          {
-            line.push(segment)
-          } else {
-            self.absolute_mappings.push(vec![segment])
-          }
+          ]);
         }
 
         match char {
@@ -108,15 +251,16 @@ impl Mapping {
             first = true;
           }
           _ => {
-            original_column += 1;
-            self.generated_code_column += 1;
+            let len = self.column_unit.len_of_char(char);
+            original_column += len as i64;
+            self.generated_code_column += len;
             first = false;
           }
         }
       }
     }
 
-    self.advance(chunk.borrow().outro.as_str());
+    self.advance(chunk.outro.as_str());
   }
 
   pub fn advance(&mut self, str: &str) {
@@ -128,7 +272,7 @@ impl Mapping {
 
     let mut i = lines.len();
     while i > 1 {
-      self.absolute_mappings.push(Vec::default());
+      self.output.push_line();
       self.generated_code_column = 0;
       i -= 1;
     }
@@ -136,7 +280,40 @@ impl Mapping {
     self.generated_code_line += (lines.len() - 1) as u32;
 
     // save starting column for later use
-    self.generated_code_column += lines.last().unwrap().len() as u32;
+    self.generated_code_column += self.column_unit.len_of_str(lines.last().unwrap());
+  }
+
+  // the generated line and column which the next chunk starts at
+  pub fn generated_position(&self) -> (u32, u32) {
+    (self.generated_code_line, self.generated_code_column)
+  }
+}
+
+impl Mapping {
+  // Replaces each segment with the one it's traced to through `input_mappings`, the absolute mappings of an input sourcemap.
+  // `trace` receives a segment and the input segment covering its original position, segments without one are dropped.
+  pub fn remap<F>(&mut self, input_mappings: &Mappings, mut trace: F)
+  where
+    F: FnMut(&Segment, &Segment) -> Segment,
+  {
+    for line in self.output.iter_mut() {
+      *line = line
+        .iter()
+        .filter_map(|segment| {
+          let input_line = input_mappings.get(segment[2] as usize)?;
+
+          // the input segment with the greatest generated column less than or equal to the original column
+          let index = input_line.partition_point(|input_segment| input_segment[0] <= segment[3]);
+          let input_segment = input_line.get(index.checked_sub(1)?)?;
+
+          if input_segment.len() < 4 {
+            return None;
+          }
+
+          Some(trace(segment, input_segment))
+        })
+        .collect();
+    }
   }
 
   // absolute to relative
@@ -144,9 +321,10 @@ impl Mapping {
     let mut source_index: i64 = 0;
     let mut original_line: i64 = 0;
     let mut original_column: i64 = 0;
+    let mut name_index: i64 = 0;
 
     let decoded_mappings = self
-      .absolute_mappings
+      .output
       .iter()
       .map(|line| {
         let mut generated_column: i64 = 0;
@@ -155,21 +333,34 @@ impl Mapping {
           .iter()
           .map(|segment| {
             let generated_column_offset = segment[0] - generated_column;
+            generated_column = segment[0];
+
+            // a segment which isn't mapped to any source
+            if segment.len() < 4 {
+              return vec![generated_column_offset];
+            }
+
             let source_index_offset = segment[1] - source_index;
             let original_line_offset = segment[2] - original_line;
             let original_column_offset = segment[3] - original_column;
 
-            generated_column = segment[0];
             source_index = segment[1];
             original_line = segment[2];
             original_column = segment[3];
 
-            vec![
+            let mut relative_segment = vec![
               generated_column_offset,
               source_index_offset,
               original_line_offset,
               original_column_offset,
-            ]
+            ];
+
+            if let Some(&segment_name_index) = segment.get(4) {
+              relative_segment.push(segment_name_index - name_index);
+              name_index = segment_name_index;
+            }
+
+            relative_segment
           })
           .collect::<Line>()
       })
@@ -178,49 +369,124 @@ impl Mapping {
     decoded_mappings
   }
 
-  // generate encoded mappings, mappings are encoded relatively
+  // relative to absolute, the reverse of `get_decoded_mappings`
   #[allow(clippy::ptr_arg)]
-  pub fn generate_encoded_mappings(decoded_mappings: &Mappings) -> Result<String> {
-    let mut encoded_mappings: Vec<String> = vec![];
+  pub fn get_absolute_mappings(decoded_mappings: &Mappings) -> Mappings {
+    let mut source_index: i64 = 0;
+    let mut original_line: i64 = 0;
+    let mut original_column: i64 = 0;
+    let mut name_index: i64 = 0;
+
+    decoded_mappings
+      .iter()
+      .map(|line| {
+        let mut generated_column: i64 = 0;
 
-    for line in decoded_mappings.iter() {
-      let mut line_str: Vec<String> = vec![];
+        let mut line = line
+          .iter()
+          .filter(|segment| !segment.is_empty())
+          .map(|segment| {
+            generated_column += segment[0];
+            let mut absolute_segment = vec![generated_column];
+
+            if segment.len() >= 4 {
+              source_index += segment[1];
+              original_line += segment[2];
+              original_column += segment[3];
+              absolute_segment.extend([source_index, original_line, original_column]);
+            }
+
+            if let Some(&segment_name_index) = segment.get(4) {
+              name_index += segment_name_index;
+              absolute_segment.push(name_index);
+            }
+
+            absolute_segment
+          })
+          .collect::<Line>();
+
+        line.sort_by_key(|segment| segment[0]);
+        line
+      })
+      .collect::<Mappings>()
+  }
+
+  // decode VLQ encoded mappings, segments of the result are relative, just like `get_decoded_mappings`
+  pub fn decode_mappings(mappings: &str) -> Result<Mappings> {
+    mappings
+      .split(';')
+      .map(|line| {
+        line
+          .split(',')
+          .filter(|segment| !segment.is_empty())
+          .map(|segment| {
+            let mut input = segment.bytes().peekable();
+            let mut decoded_segment: Segment = vec![];
 
+            while input.peek().is_some() {
+              decoded_segment.push(vlq::decode(&mut input)?);
+            }
+
+            Ok(decoded_segment)
+          })
+          .collect::<Result<Line>>()
+      })
+      .collect()
+  }
+
+  // encode absolute mappings, without converting them to relative ones first
+  pub fn get_encoded_mappings(&self) -> String {
+    let mut encoder = MappingsEncoder::default();
+
+    for line in self.output.iter() {
+      encoder.push_line();
       for segment in line.iter() {
-        let mut segment_str: Vec<String> = vec![];
+        encoder.push_segment(segment);
+      }
+    }
+
+    encoder.into_mappings()
+  }
 
-        for item in segment.iter() {
-          let mut vlq_output: Vec<u8> = vec![];
+  // generate encoded mappings, mappings are encoded relatively
+  #[allow(clippy::ptr_arg)]
+  pub fn generate_encoded_mappings(decoded_mappings: &Mappings) -> String {
+    let mut encoded_mappings = String::new();
+
+    for (i, line) in decoded_mappings.iter().enumerate() {
+      if i > 0 {
+        encoded_mappings.push(';');
+      }
 
-          vlq::encode(item.to_owned(), &mut vlq_output)?;
-          segment_str.push(String::from_utf8(vlq_output)?);
+      for (j, segment) in line.iter().enumerate() {
+        if j > 0 {
+          encoded_mappings.push(',');
         }
 
-        line_str.push(segment_str.join(""));
+        for &item in segment.iter() {
+          encode_vlq(item, &mut encoded_mappings);
+        }
       }
-
-      encoded_mappings.push(line_str.join(","));
     }
 
-    let encoded_mappings_str = encoded_mappings.join(";");
-
-    Ok(encoded_mappings_str)
+    encoded_mappings
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::Mapping;
+  use crate::magic_string::{ColumnUnit, Hires};
 
   #[test]
   fn absolute_mapping_to_relative_mapping() {
-    let mut mapping = Mapping::new(false);
+    let mut mapping = Mapping::new(Hires::Off, ColumnUnit::default());
 
     mapping
-      .absolute_mappings
+      .output
       .push(vec![vec![3, 1, 0, 1], vec![4, 1, 0, 1]]);
     mapping
-      .absolute_mappings
+      .output
       .push(vec![vec![5, 5, 2, 9], vec![6, 6, 3, 10]]);
 
     let decoded_mappings = mapping.get_decoded_mappings();
@@ -233,4 +499,99 @@ mod tests {
       ]
     )
   }
+
+  #[test]
+  fn relative_mapping_to_absolute_mapping() {
+    let decoded_mappings = vec![
+      vec![vec![0, 0, 0, 0], vec![3, 0, 0, 3, 1], vec![3, 0, 0, 3]],
+      vec![vec![2, 0, 1, -4, -1], vec![3, 0, 0, 3, 1]],
+      vec![vec![0]],
+    ];
+
+    assert_eq!(
+      Mapping::get_absolute_mappings(&decoded_mappings),
+      vec![
+        vec![vec![0, 0, 0, 0], vec![3, 0, 0, 3, 1], vec![6, 0, 0, 6]],
+        vec![vec![2, 0, 1, 2, 0], vec![5, 0, 1, 5, 1]],
+        vec![vec![0]],
+      ]
+    );
+  }
+
+  #[test]
+  fn decode_mappings() {
+    assert_eq!(
+      Mapping::decode_mappings("AAAA,SAASA,GAAG;;AACZ").unwrap(),
+      vec![
+        vec![vec![0, 0, 0, 0], vec![9, 0, 0, 9, 0], vec![3, 0, 0, 3]],
+        vec![],
+        vec![vec![0, 0, 1, -12]],
+      ]
+    );
+    assert_eq!(
+      Mapping::decode_mappings("").unwrap(),
+      vec![vec![]] as Vec<Vec<Vec<i64>>>
+    );
+  }
+
+  #[test]
+  fn absolute_mapping_with_names_to_relative_mapping() {
+    let mut mapping = Mapping::new(Hires::Off, ColumnUnit::default());
+
+    mapping.output.push(vec![
+      vec![0, 0, 0, 0],
+      vec![3, 0, 0, 3, 1],
+      vec![6, 0, 0, 6],
+    ]);
+    mapping
+      .output
+      .push(vec![vec![2, 0, 1, 2, 0], vec![5, 0, 1, 5, 1]]);
+
+    let decoded_mappings = mapping.get_decoded_mappings();
+
+    assert_eq!(
+      &decoded_mappings,
+      &vec![
+        vec![vec![0, 0, 0, 0], vec![3, 0, 0, 3, 1], vec![3, 0, 0, 3]],
+        vec![vec![2, 0, 1, -4, -1], vec![3, 0, 0, 3, 1]],
+      ]
+    )
+  }
+
+  #[test]
+  fn encode_mappings() {
+    let decoded_mappings = vec![
+      vec![vec![0, 0, 0, 0], vec![16, 0, -16, 15, 1]],
+      vec![],
+      vec![vec![1], vec![i32::MAX as i64, 0, i32::MIN as i64, 0]],
+    ];
+
+    let encoded_mappings = Mapping::generate_encoded_mappings(&decoded_mappings);
+
+    assert_eq!(&encoded_mappings[..16], "AAAA,gBAhBeC;;C,");
+    assert_eq!(
+      Mapping::decode_mappings(encoded_mappings.as_str()).unwrap(),
+      decoded_mappings
+    );
+  }
+
+  #[test]
+  fn encode_absolute_mappings() {
+    let absolute_mappings = vec![
+      vec![vec![0, 0, 0, 0], vec![3, 0, 0, 3, 1], vec![6, 0, 0, 6]],
+      vec![vec![2]],
+      vec![vec![2, 0, 1, 2, 0], vec![5, 0, 1, 5, 1]],
+    ];
+
+    let mut mapping = Mapping::from_absolute_mappings(absolute_mappings.to_owned());
+
+    assert_eq!(
+      mapping.get_encoded_mappings(),
+      Mapping::generate_encoded_mappings(&mapping.get_decoded_mappings())
+    );
+    assert_eq!(
+      mapping.get_encoded_mappings(),
+      "AAAA,GAAGC,GAAG;E;EACJD,GAAGC"
+    );
+  }
 }
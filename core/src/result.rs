@@ -11,9 +11,12 @@ pub enum MagicStringErrorType {
 
   JSONSerializationError,
 
+  DataUrlError,
+
   VlqUnexpectedEof,
   VlqInvalidBase64,
   VlqOverflow,
+  VlqNegativeValue,
 
   RegexSyntaxError,
   RegexCompiledTooBig,
@@ -23,6 +26,9 @@ pub enum MagicStringErrorType {
   MagicStringCrossChunkError,
   MagicStringDoubleSplitError,
   MagicStringDoubleEditError,
+  MagicStringReplacedAnchorError,
+  MagicStringIllegalSourceError,
+  MagicStringCharBoundaryError,
   MagicStringUnknownError,
 
   Default,
@@ -108,6 +114,13 @@ impl From<string::FromUtf8Error> for Error {
   }
 }
 
+impl From<base64::DecodeError> for Error {
+  #[inline]
+  fn from(_: base64::DecodeError) -> Self {
+    Error::new_with_reason(MagicStringErrorType::DataUrlError, "invalid base64 content")
+  }
+}
+
 impl From<serde_json::Error> for Error {
   #[inline]
   fn from(_: serde_json::Error) -> Self {
@@ -133,6 +146,10 @@ impl From<Error> for napi::Error {
         reason.push_str("JSON Serialization Error");
       }
 
+      MagicStringErrorType::DataUrlError => {
+        reason.push_str("Data URL Error");
+      }
+
       MagicStringErrorType::VlqUnexpectedEof => {
         reason.push_str("Vlq Unexpected Eof");
       }
@@ -142,6 +159,9 @@ impl From<Error> for napi::Error {
       MagicStringErrorType::VlqOverflow => {
         reason.push_str("Vlq Overflow");
       }
+      MagicStringErrorType::VlqNegativeValue => {
+        reason.push_str("Vlq Negative Value");
+      }
 
       MagicStringErrorType::RegexSyntaxError => {
         reason.push_str("Regex Syntax Error");
@@ -168,6 +188,15 @@ impl From<Error> for napi::Error {
       MagicStringErrorType::MagicStringDoubleEditError => {
         reason.push_str("Magic String Double Edit Error");
       }
+      MagicStringErrorType::MagicStringReplacedAnchorError => {
+        reason.push_str("Magic String Replaced Anchor Error");
+      }
+      MagicStringErrorType::MagicStringIllegalSourceError => {
+        reason.push_str("Magic String Illegal Source Error");
+      }
+      MagicStringErrorType::MagicStringCharBoundaryError => {
+        reason.push_str("Magic String Char Boundary Error");
+      }
 
       MagicStringErrorType::Default => {
         reason.push_str(
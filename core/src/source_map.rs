@@ -1,24 +1,35 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::magic_string::DecodedMap;
-use crate::mapping::Mapping;
+use crate::mapping::{Mapping, Mappings};
 use crate::result::Result;
+use crate::{Error, MagicStringErrorType};
 
 // current specification version
 static VERSION: u8 = 3;
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceMap {
   pub version: u8,
   pub mappings: String,
+  #[serde(default)]
   pub names: Vec<String>,
   pub sources: Vec<Option<String>>,
+  #[serde(default)]
   pub sources_content: Vec<Option<String>>,
+  #[serde(default)]
   pub file: Option<String>,
   #[serde(skip_serializing_if = "Option::is_none")]
   #[serde(default)]
   pub source_root: Option<String>,
+  #[serde(rename = "x_google_ignoreList")]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  #[serde(default)]
+  pub ignore_list: Vec<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  #[serde(default)]
+  pub debug_id: Option<String>,
 }
 
 impl SourceMap {
@@ -41,6 +52,8 @@ impl SourceMap {
         .collect(),
       source_root: source_root.map(|s| s.to_owned()),
       sources: sources.iter().map(|s| s.map(|s| s.to_owned())).collect(),
+      ignore_list: vec![],
+      debug_id: None,
     }
   }
 
@@ -50,7 +63,7 @@ impl SourceMap {
   ///
   /// Example:
   /// ```
-  /// use magic_string::{MagicString, GenerateDecodedMapOptions, SourceMap};
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions, Hires, SourceMap};
   ///
   /// let mut s = MagicString::new("export default React");
   /// s.prepend("import React from 'react'\n");
@@ -60,23 +73,137 @@ impl SourceMap {
   ///   source: Some("index.ts".to_owned()),
   ///   source_root: Some("./".to_owned()),
   ///   include_content: true,
-  ///   hires: false,
+  ///   hires: Hires::Off,
+  ///   ..GenerateDecodedMapOptions::default()
   /// }).expect("failed to generate decoded map");
   ///
   /// SourceMap::new_from_decoded(decoded_map);
   /// ```
   pub fn new_from_decoded(decoded_map: DecodedMap) -> Result<Self> {
-    Ok(Self {
+    let mappings = Mapping::generate_encoded_mappings(&decoded_map.mappings);
+
+    Ok(Self::new_with_encoded_mappings(mappings, decoded_map))
+  }
+
+  // Same as `new_from_decoded` but with mappings which are already encoded, `mappings` of `decoded_map` are ignored.
+  pub(crate) fn new_with_encoded_mappings(mappings: String, decoded_map: DecodedMap) -> Self {
+    Self {
       version: VERSION,
       file: decoded_map.file,
-      mappings: Mapping::generate_encoded_mappings(&decoded_map.mappings)?,
+      mappings,
       names: decoded_map.names,
       source_root: decoded_map.source_root,
       sources_content: decoded_map.sources_content,
       sources: decoded_map.sources,
+      ignore_list: decoded_map.ignore_list,
+      debug_id: decoded_map.debug_id,
+    }
+  }
+
+  /// ## Create a SourceMap instance from JSON
+  ///
+  /// Parses a version 3 sourcemap. `mappings` are kept encoded, use `to_decoded_map` to decode them.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::SourceMap;
+  ///
+  /// let map = SourceMap::from_json(r#"{"version":3,"mappings":"AAAA,GAAS","names":[],"sources":["input.md"]}"#).unwrap();
+  ///
+  /// assert_eq!(map.sources, vec![Some("input.md".to_owned())]);
+  /// assert_eq!(map.mappings, "AAAA,GAAS");
+  /// ```
+  pub fn from_json(json: &str) -> Result<Self> {
+    let map: Self = serde_json::from_str(json)?;
+
+    if map.version != VERSION {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::JSONSerializationError,
+        format!("unsupported sourcemap version {}", map.version).as_str(),
+      ));
+    }
+
+    Ok(map)
+  }
+
+  /// ## Create a SourceMap instance from a `data:` URL
+  ///
+  /// Parses an inline sourcemap, which is either base64 or percent encoded, e.g. the one generated by `to_url`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions, SourceMap};
+  ///
+  /// let s = MagicString::new("abc");
+  /// let url = s.generate_map(GenerateDecodedMapOptions::default()).unwrap().to_url().unwrap();
+  ///
+  /// assert_eq!(SourceMap::from_url(url.as_str()).unwrap().mappings, "AAAA");
+  /// ```
+  pub fn from_url(url: &str) -> Result<Self> {
+    let (meta, data) = url
+      .strip_prefix("data:")
+      .and_then(|url| url.split_once(','))
+      .ok_or_else(|| {
+        Error::new_with_reason(MagicStringErrorType::DataUrlError, "not a data URL")
+      })?;
+
+    let mut params = meta.split(';');
+    let mime = params.next().unwrap_or_default();
+    if !mime.is_empty() && mime != "application/json" {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::DataUrlError,
+        format!("unsupported media type {}", mime).as_str(),
+      ));
+    }
+
+    let json = if params.any(|param| param == "base64") {
+      String::from_utf8(base64::decode(data)?)?
+    } else {
+      percent_decode(data)?
+    };
+
+    Self::from_json(json.as_str())
+  }
+
+  /// ## Decode SourceMap
+  ///
+  /// Decodes `mappings` and returns a `DecodedMap`, the reverse of `new_from_decoded`.
+  pub fn to_decoded_map(&self) -> Result<DecodedMap> {
+    Ok(DecodedMap {
+      file: self.file.to_owned(),
+      sources: self.sources.to_owned(),
+      source_root: self.source_root.to_owned(),
+      sources_content: self.sources_content.to_owned(),
+      names: self.names.to_owned(),
+      mappings: decode_mappings(self.mappings.as_str())?,
+      ignore_list: self.ignore_list.to_owned(),
+      debug_id: self.debug_id.to_owned(),
     })
   }
 
+  /// ## Debug ID comment
+  ///
+  /// Returns the `//# debugId=` comment to be appended to the generated code, which ties it to this sourcemap, if `debug_id` is set.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{MagicString, GenerateDecodedMapOptions};
+  ///
+  /// let s = MagicString::new("abc");
+  /// let map = s.generate_map(GenerateDecodedMapOptions {
+  ///   debug_id: Some("85314830-023f-4cf1-a267-535f4e37bb17".to_owned()),
+  ///   ..GenerateDecodedMapOptions::default()
+  /// }).unwrap();
+  ///
+  /// assert_eq!(map.debug_id_comment(), Some("//# debugId=85314830-023f-4cf1-a267-535f4e37bb17".to_owned()));
+  /// ```
+  pub fn debug_id_comment(&self) -> Option<String> {
+    self
+      .debug_id
+      .as_ref()
+      .map(|debug_id| format!("//# debugId={}", debug_id))
+  }
+
   /// ## Generate SourceMap in JSON format
   pub fn to_string(&self) -> Result<String> {
     Ok(serde_json::to_string(self)?)
@@ -92,3 +219,340 @@ impl SourceMap {
     ))
   }
 }
+
+/// How the generated code refers to its sourcemap, see `MagicString::finalize`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceMappingUrlStyle {
+  /// `//# sourceMappingURL=data:application/json;...`, the sourcemap is inlined as a data URL.
+  Inline,
+  /// `//# sourceMappingURL=<path>`, the sourcemap is expected to be written to `path`, relative to the generated code.
+  External(String),
+  /// No comment at all, the sourcemap is only known to the tools which are told where it is.
+  Hidden,
+  /// `/*# sourceMappingURL=<path> */` for CSS, the sourcemap is inlined as a data URL if `path` is `None`.
+  CssComment(Option<String>),
+}
+
+impl SourceMappingUrlStyle {
+  // Appends the `debugId` (if any) and `sourceMappingURL` comments of `map` to `code`, each on its own line.
+  pub(crate) fn append_comments(&self, code: &mut String, map: &SourceMap) -> Result {
+    let (start, end) = match self {
+      Self::CssComment(_) => ("/*#", " */"),
+      _ => ("//#", ""),
+    };
+
+    let url = match self {
+      Self::Inline | Self::CssComment(None) => Some(map.to_url()?),
+      Self::External(path) | Self::CssComment(Some(path)) => Some(path.to_owned()),
+      Self::Hidden => None,
+    };
+
+    let comments = [
+      map
+        .debug_id
+        .as_ref()
+        .map(|debug_id| format!("{} debugId={}{}", start, debug_id, end)),
+      url.map(|url| format!("{} sourceMappingURL={}{}", start, url, end)),
+    ];
+
+    for comment in comments.into_iter().flatten() {
+      if !code.is_empty() && !code.ends_with('\n') {
+        code.push('\n');
+      }
+      code.push_str(comment.as_str());
+    }
+
+    Ok(())
+  }
+}
+
+/// Generated position where a section of a `SectionedSourceMap` starts, 0-based.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SectionOffset {
+  pub line: u32,
+  pub column: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Section {
+  pub offset: SectionOffset,
+  pub map: SourceMap,
+}
+
+/// An index sourcemap, which consists of the sourcemaps of multiple sections of the generated code.
+///
+/// Sections are sorted by `offset` and must not overlap.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SectionedSourceMap {
+  pub version: u8,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  #[serde(default)]
+  pub file: Option<String>,
+  pub sections: Vec<Section>,
+}
+
+impl SectionedSourceMap {
+  pub fn new(file: Option<&str>, sections: Vec<Section>) -> Self {
+    Self {
+      version: VERSION,
+      file: file.map(|f| f.to_owned()),
+      sections,
+    }
+  }
+
+  /// ## Create a SectionedSourceMap instance from JSON
+  ///
+  /// Parses a version 3 index sourcemap. Sections referencing their sourcemap by `url` are not supported.
+  pub fn from_json(json: &str) -> Result<Self> {
+    let map: Self = serde_json::from_str(json)?;
+
+    if map.version != VERSION {
+      return Err(Error::new_with_reason(
+        MagicStringErrorType::JSONSerializationError,
+        format!("unsupported sourcemap version {}", map.version).as_str(),
+      ));
+    }
+
+    Ok(map)
+  }
+
+  /// ## Flatten
+  ///
+  /// Merges all sections into a regular `SourceMap`, shifting their mappings by `offset`.
+  /// `sources` and `names` shared by multiple sections are listed only once, and `source_root` of each section is prepended to its `sources`.
+  /// Sources ignored by any section are listed in `ignore_list`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{Section, SectionOffset, SectionedSourceMap, SourceMap};
+  ///
+  /// let map = SectionedSourceMap::new(
+  ///   Some("bundle.js"),
+  ///   vec![
+  ///     Section {
+  ///       offset: SectionOffset { line: 0, column: 0 },
+  ///       map: SourceMap::new("AAAA", None, vec![], vec![], None, vec![Some("a.js")]),
+  ///     },
+  ///     Section {
+  ///       offset: SectionOffset { line: 1, column: 4 },
+  ///       map: SourceMap::new("AAAA", None, vec![], vec![], None, vec![Some("b.js")]),
+  ///     },
+  ///   ],
+  /// );
+  /// let flattened = map.flatten().unwrap();
+  ///
+  /// assert_eq!(flattened.sources, vec![Some("a.js".to_owned()), Some("b.js".to_owned())]);
+  /// assert_eq!(flattened.mappings, "AAAA;ICAA");
+  /// ```
+  pub fn flatten(&self) -> Result<SourceMap> {
+    let mut sources: Vec<Option<String>> = vec![];
+    let mut sources_content: Vec<Option<String>> = vec![];
+    let mut names: Vec<String> = vec![];
+    let mut ignore_list: Vec<u32> = vec![];
+    let mut absolute_mappings: Mappings = vec![];
+
+    for section in self.sections.iter() {
+      let map = &section.map;
+
+      let source_indices = map
+        .sources
+        .iter()
+        .enumerate()
+        .map(|(i, source)| {
+          let source = source.as_deref().map(|source| match map.source_root {
+            Some(ref source_root) if !source_root.is_empty() && !source_root.ends_with('/') => {
+              format!("{}/{}", source_root, source)
+            }
+            Some(ref source_root) => format!("{}{}", source_root, source),
+            None => source.to_owned(),
+          });
+
+          let index = source
+            .as_ref()
+            .and_then(|source| sources.iter().position(|s| s.as_ref() == Some(source)))
+            .unwrap_or_else(|| {
+              sources.push(source);
+              sources_content.push(map.sources_content.get(i).cloned().flatten());
+              sources.len() - 1
+            });
+
+          index as i64
+        })
+        .collect::<Vec<_>>();
+
+      for &ignored_source_index in map.ignore_list.iter() {
+        if let Some(&source_index) = source_indices.get(ignored_source_index as usize) {
+          ignore_list.push(source_index as u32);
+        }
+      }
+
+      let name_indices = map
+        .names
+        .iter()
+        .map(|name| {
+          let index = names.iter().position(|n| n == name).unwrap_or_else(|| {
+            names.push(name.to_owned());
+            names.len() - 1
+          });
+
+          index as i64
+        })
+        .collect::<Vec<_>>();
+
+      let mappings = Mapping::get_absolute_mappings(&decode_mappings(map.mappings.as_str())?);
+
+      for (i, line) in mappings.into_iter().enumerate() {
+        let generated_line = section.offset.line as usize + i;
+        if absolute_mappings.len() <= generated_line {
+          absolute_mappings.resize(generated_line + 1, vec![]);
+        }
+
+        // only the first line of a section starts at the offset column
+        let column_offset = if i == 0 {
+          section.offset.column as i64
+        } else {
+          0
+        };
+
+        for segment in line {
+          let mut flattened_segment = vec![segment[0] + column_offset];
+
+          if segment.len() >= 4 {
+            match source_indices.get(segment[1] as usize) {
+              Some(&source_index) => {
+                flattened_segment.extend([source_index, segment[2], segment[3]]);
+              }
+              None => continue,
+            }
+          }
+
+          if let Some(&name_index) = segment
+            .get(4)
+            .and_then(|&name_index| name_indices.get(name_index as usize))
+          {
+            flattened_segment.push(name_index);
+          }
+
+          absolute_mappings[generated_line].push(flattened_segment);
+        }
+      }
+    }
+
+    if sources_content.iter().all(|content| content.is_none()) {
+      sources_content.clear();
+    }
+    ignore_list.sort_unstable();
+    ignore_list.dedup();
+
+    Ok(SourceMap {
+      version: VERSION,
+      file: self.file.to_owned(),
+      mappings: Mapping::from_absolute_mappings(absolute_mappings).get_encoded_mappings(),
+      names,
+      source_root: None,
+      sources_content,
+      sources,
+      ignore_list,
+      debug_id: None,
+    })
+  }
+
+  /// ## Generate SectionedSourceMap in JSON format
+  pub fn to_string(&self) -> Result<String> {
+    Ok(serde_json::to_string(self)?)
+  }
+
+  /// ## Generate inline SectionedSourceMap
+  pub fn to_url(&self) -> Result<String> {
+    let str = Self::to_string(self)?;
+
+    Ok(format!(
+      "data:application/json;charset=utf-8;base64,{}",
+      base64::encode(str)
+    ))
+  }
+}
+
+/// ## Decode mappings
+///
+/// Decodes VLQ encoded `mappings` of a sourcemap, the result is in the same form as `mappings` of `DecodedMap`,
+/// i.e. each field of a segment is relative to the previous one.
+/// Returns an error if `mappings` contains invalid base64 characters, a segment is truncated,
+/// or a field accumulates to a negative value.
+///
+/// Example:
+/// ```
+/// use magic_string::decode_mappings;
+///
+/// assert_eq!(decode_mappings("AAAA,GAAS;AACA").unwrap(), vec![vec![vec![0, 0, 0, 0], vec![3, 0, 0, 9]], vec![vec![0, 0, 1, 0]]]);
+/// assert!(decode_mappings("AA!A").is_err());
+/// assert!(decode_mappings("AAAA,CAAD").is_err());
+/// ```
+pub fn decode_mappings(mappings: &str) -> Result<Mappings> {
+  let decoded_mappings = Mapping::decode_mappings(mappings)?;
+
+  const FIELDS: [&str; 5] = [
+    "generated column",
+    "source index",
+    "original line",
+    "original column",
+    "name index",
+  ];
+  // fields are relative to the previous segment, generated column is reset on each line
+  let mut absolute = [0i64; 5];
+
+  for line in decoded_mappings.iter() {
+    absolute[0] = 0;
+
+    for segment in line.iter() {
+      if !matches!(segment.len(), 1 | 4 | 5) {
+        return Err(Error::new_with_reason(
+          MagicStringErrorType::VlqUnexpectedEof,
+          format!(
+            "a segment should have 1, 4 or 5 fields, got {}",
+            segment.len()
+          )
+          .as_str(),
+        ));
+      }
+
+      for (i, field) in segment.iter().enumerate() {
+        absolute[i] += field;
+        if absolute[i] < 0 {
+          return Err(Error::new_with_reason(
+            MagicStringErrorType::VlqNegativeValue,
+            format!("{} should not be negative, got {}", FIELDS[i], absolute[i]).as_str(),
+          ));
+        }
+      }
+    }
+  }
+
+  Ok(decoded_mappings)
+}
+
+fn percent_decode(s: &str) -> Result<String> {
+  let invalid = || {
+    Error::new_with_reason(
+      MagicStringErrorType::DataUrlError,
+      "invalid percent encoding",
+    )
+  };
+
+  let mut bytes: Vec<u8> = vec![];
+  let mut input = s.bytes();
+  while let Some(byte) = input.next() {
+    if byte == b'%' {
+      let hex = [
+        input.next().ok_or_else(invalid)?,
+        input.next().ok_or_else(invalid)?,
+      ];
+      let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+      bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+    } else {
+      bytes.push(byte);
+    }
+  }
+
+  Ok(String::from_utf8(bytes)?)
+}
@@ -0,0 +1,244 @@
+use crate::{
+  magic_string::DecodedMap,
+  mapping::{Mapping, Mappings},
+  result::Result,
+  source_map::{decode_mappings, SourceMap},
+};
+
+/// Which segment to pick if there's no segment at the exact position being looked up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Bias {
+  /// The closest segment before the position
+  #[default]
+  GreatestLowerBound,
+  /// The closest segment after the position
+  LeastUpperBound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalPosition {
+  pub source: Option<String>,
+  pub line: u32,
+  pub column: u32,
+  pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneratedPosition {
+  pub line: u32,
+  pub column: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingItem {
+  pub generated_line: u32,
+  pub generated_column: u32,
+  pub source: Option<String>,
+  pub original_line: Option<u32>,
+  pub original_column: Option<u32>,
+  pub name: Option<String>,
+}
+
+// (source index, original line, original column, generated line, generated column)
+type OriginalMapping = (i64, i64, i64, u32, i64);
+
+#[derive(Debug, Clone)]
+pub struct SourceMapConsumer {
+  sources: Vec<Option<String>>,
+  names: Vec<String>,
+
+  // absolute segments of each generated line, sorted by generated column
+  generated_mappings: Mappings,
+  // sorted by source, original line and original column, then generated line and generated column
+  original_mappings: Vec<OriginalMapping>,
+}
+
+impl SourceMapConsumer {
+  /// ## Create a new `SourceMapConsumer` instance
+  ///
+  /// Decodes the mappings of `map` for querying. Lines and columns are all 0-based, and `sources` are matched as is, without `source_root`.
+  ///
+  /// Example:
+  /// ```
+  /// use magic_string::{Bias, GenerateDecodedMapOptions, Hires, MagicString, SourceMapConsumer};
+  ///
+  /// let mut s = MagicString::new("abc\ndef");
+  /// s.prepend("// header\n");
+  ///
+  /// let map = s.generate_map(GenerateDecodedMapOptions {
+  ///   source: Some("input.js".to_owned()),
+  ///   hires: Hires::Full,
+  ///   ..GenerateDecodedMapOptions::default()
+  /// }).unwrap();
+  /// let consumer = SourceMapConsumer::new(&map).unwrap();
+  ///
+  /// let position = consumer.original_position_for(2, 1, Bias::GreatestLowerBound).unwrap();
+  /// assert_eq!((position.source.as_deref(), position.line, position.column), (Some("input.js"), 1, 1));
+  /// ```
+  pub fn new(map: &SourceMap) -> Result<Self> {
+    Ok(Self::from_decoded_mappings(
+      map.sources.to_owned(),
+      map.names.to_owned(),
+      &decode_mappings(map.mappings.as_str())?,
+    ))
+  }
+
+  /// ## Create a new `SourceMapConsumer` instance from a decoded map
+  ///
+  /// `DecodedMap` can be created by utilizing `generate_decoded_map`.
+  pub fn new_from_decoded(map: &DecodedMap) -> Self {
+    Self::from_decoded_mappings(map.sources.to_owned(), map.names.to_owned(), &map.mappings)
+  }
+
+  /// ## Original position for
+  ///
+  /// Returns the original position of the generated `line` and `column`.
+  /// If there's no segment at the exact column, the closest one on the same line is picked according to `bias`.
+  /// Returns `None` if there's no such segment, or the segment isn't mapped to any source.
+  pub fn original_position_for(
+    &self,
+    line: u32,
+    column: u32,
+    bias: Bias,
+  ) -> Option<OriginalPosition> {
+    let segments = self.generated_mappings.get(line as usize)?;
+    let column = column as i64;
+
+    let segment = match bias {
+      Bias::GreatestLowerBound => {
+        let index = segments.partition_point(|segment| segment[0] <= column);
+        segments.get(index.checked_sub(1)?)?
+      }
+      Bias::LeastUpperBound => {
+        let index = segments.partition_point(|segment| segment[0] < column);
+        segments.get(index)?
+      }
+    };
+
+    if segment.len() < 4 {
+      return None;
+    }
+
+    Some(OriginalPosition {
+      source: self.source_at(segment[1]),
+      line: segment[2] as u32,
+      column: segment[3] as u32,
+      name: segment
+        .get(4)
+        .and_then(|&name_index| self.name_at(name_index)),
+    })
+  }
+
+  /// ## Generated positions for
+  ///
+  /// Returns all generated positions of the original `line` and `column` of `source`, in the order of generated positions.
+  /// If there's no segment at the exact column, the ones with the closest column after it on the same line are returned.
+  pub fn generated_positions_for(
+    &self,
+    source: &str,
+    line: u32,
+    column: u32,
+  ) -> Vec<GeneratedPosition> {
+    let source_index = match self
+      .sources
+      .iter()
+      .position(|s| s.as_deref() == Some(source))
+    {
+      Some(source_index) => source_index as i64,
+      None => return vec![],
+    };
+    let line = line as i64;
+
+    let index = self
+      .original_mappings
+      .partition_point(|&(s, l, c, _, _)| (s, l, c) < (source_index, line, column as i64));
+
+    let column = match self.original_mappings.get(index) {
+      Some(&(s, l, c, _, _)) if s == source_index && l == line => c,
+      _ => return vec![],
+    };
+
+    self.original_mappings[index..]
+      .iter()
+      .take_while(|&&(s, l, c, _, _)| s == source_index && l == line && c == column)
+      .map(
+        |&(_, _, _, generated_line, generated_column)| GeneratedPosition {
+          line: generated_line,
+          column: generated_column as u32,
+        },
+      )
+      .collect()
+  }
+
+  /// ## Each mapping
+  ///
+  /// Iterates over all mappings in the order of generated positions.
+  pub fn each_mapping<F>(&self, mut f: F)
+  where
+    F: FnMut(MappingItem),
+  {
+    for (generated_line, segments) in self.generated_mappings.iter().enumerate() {
+      for segment in segments {
+        let mapped = segment.len() >= 4;
+
+        f(MappingItem {
+          generated_line: generated_line as u32,
+          generated_column: segment[0] as u32,
+          source: if mapped {
+            self.source_at(segment[1])
+          } else {
+            None
+          },
+          original_line: mapped.then(|| segment[2] as u32),
+          original_column: mapped.then(|| segment[3] as u32),
+          name: segment
+            .get(4)
+            .and_then(|&name_index| self.name_at(name_index)),
+        });
+      }
+    }
+  }
+
+  fn from_decoded_mappings(
+    sources: Vec<Option<String>>,
+    names: Vec<String>,
+    decoded_mappings: &Mappings,
+  ) -> Self {
+    let generated_mappings = Mapping::get_absolute_mappings(decoded_mappings);
+
+    let mut original_mappings: Vec<OriginalMapping> = generated_mappings
+      .iter()
+      .enumerate()
+      .flat_map(|(generated_line, segments)| {
+        segments
+          .iter()
+          .filter(|segment| segment.len() >= 4)
+          .map(move |segment| {
+            (
+              segment[1],
+              segment[2],
+              segment[3],
+              generated_line as u32,
+              segment[0],
+            )
+          })
+      })
+      .collect();
+    original_mappings.sort_unstable();
+
+    Self {
+      sources,
+      names,
+      generated_mappings,
+      original_mappings,
+    }
+  }
+
+  fn source_at(&self, source_index: i64) -> Option<String> {
+    self.sources.get(source_index as usize).cloned().flatten()
+  }
+
+  fn name_at(&self, name_index: i64) -> Option<String> {
+    self.names.get(name_index as usize).cloned()
+  }
+}
@@ -1,4 +1,6 @@
 pub mod locator {
+  use crate::magic_string::ColumnUnit;
+
   #[allow(dead_code)]
   #[derive(Debug, Clone)]
   pub struct Locator {
@@ -29,7 +31,8 @@ pub mod locator {
       }
     }
 
-    pub fn locate(&self, index: u32) -> Location {
+    // `index` is a byte offset into the original string, the returned column is counted in `column_unit`.
+    pub fn locate(&self, index: u32, column_unit: ColumnUnit) -> Location {
       let mut i = 0;
       let mut j = self.line_offsets.len();
 
@@ -42,7 +45,8 @@ pub mod locator {
         }
       }
       let line = (i - 1) as u32;
-      let column = index - self.line_offsets[line as usize];
+      let byte_column = (index - self.line_offsets[line as usize]) as usize;
+      let column = column_unit.len_of_str(&self.original_lines[line as usize][..byte_column]);
 
       (line, column)
     }
@@ -51,6 +55,7 @@ pub mod locator {
   #[cfg(test)]
   mod tests {
     use super::Locator;
+    use crate::magic_string::ColumnUnit;
 
     #[test]
     fn test() {
@@ -64,102 +69,478 @@ pub mod locator {
       assert_eq!(locator.line_offsets[1], 6);
       assert_eq!(locator.line_offsets[2], 13);
 
-      assert_eq!(locator.locate(2), (0, 2));
-      assert_eq!(locator.locate(8), (1, 2));
-      assert_eq!(locator.locate(14), (2, 1));
+      assert_eq!(locator.locate(2, ColumnUnit::Utf16), (0, 2));
+      assert_eq!(locator.locate(8, ColumnUnit::Utf16), (1, 2));
+      assert_eq!(locator.locate(14, ColumnUnit::Utf16), (2, 1));
+    }
+
+    #[test]
+    fn should_locate_in_column_unit() {
+      // "中" is 3 bytes and 1 UTF-16 code unit, "😀" is 4 bytes and 2 UTF-16 code units
+      let locator = Locator::new("a\n中😀b");
+
+      assert_eq!(locator.locate(9, ColumnUnit::Utf16), (1, 3));
+      assert_eq!(locator.locate(9, ColumnUnit::Utf8), (1, 7));
+      assert_eq!(locator.locate(9, ColumnUnit::Char), (1, 2));
+    }
+  }
+}
+
+pub mod index {
+  use crate::{magic_string::IndexUnit, Error, MagicStringErrorType, Result};
+
+  #[derive(Debug, Clone, Copy)]
+  enum Position {
+    Byte(u32),
+    // the trailing half of a surrogate pair, which has no byte offset of its own, along with the byte offset of the character
+    InsideSurrogatePair(u32, char),
+  }
+
+  // Translates indices counted in `IndexUnit` into byte offsets of the original string, and vice versa.
+  #[derive(Debug, Clone)]
+  pub struct IndexTranslator {
+    unit: IndexUnit,
+    // length of the original string counted in `IndexUnit`
+    len: usize,
+    // position of each UTF-16 code unit, left empty if they are the same as byte offsets (i.e. `IndexUnit::Utf8` or ASCII-only strings)
+    positions: Vec<Position>,
+  }
+
+  impl IndexTranslator {
+    pub fn new(original: &str, unit: IndexUnit) -> Self {
+      if unit == IndexUnit::Utf8 || original.is_ascii() {
+        return Self {
+          unit,
+          len: original.len(),
+          positions: Vec::default(),
+        };
+      }
+
+      let mut positions: Vec<Position> = vec![];
+      for (i, c) in original.char_indices() {
+        positions.push(Position::Byte(i as u32));
+        if c.len_utf16() == 2 {
+          positions.push(Position::InsideSurrogatePair(i as u32, c));
+        }
+      }
+      positions.push(Position::Byte(original.len() as u32));
+
+      Self {
+        unit,
+        len: positions.len() - 1,
+        positions,
+      }
+    }
+
+    // Resolves a negative `index` from the end of the original string, then translates it into a byte offset.
+    pub fn normalize(&self, index: i64) -> Result<u32> {
+      let len = self.len as i64;
+
+      let index = if index < 0 { index + len } else { index };
+
+      if index < 0 || index > len {
+        return Err(Error::new_with_reason(
+          MagicStringErrorType::MagicStringOutOfRangeError,
+          "index out of range",
+        ));
+      }
+
+      self.to_byte_index(index as usize)
+    }
+
+    pub fn to_byte_index(&self, index: usize) -> Result<u32> {
+      if index > self.len {
+        return Err(Error::new_with_reason(
+          MagicStringErrorType::MagicStringOutOfRangeError,
+          "index out of range",
+        ));
+      }
+
+      if self.positions.is_empty() {
+        return Ok(index as u32);
+      }
+
+      match self.positions[index] {
+        Position::Byte(byte_index) => Ok(byte_index),
+        Position::InsideSurrogatePair(_, c) => Err(char_boundary_error(index, c)),
+      }
+    }
+
+    // `byte_index` should be on a char boundary of the original string.
+    pub fn byte_to_index(&self, byte_index: u32) -> usize {
+      if self.positions.is_empty() {
+        return byte_index as usize;
+      }
+
+      self.positions.partition_point(|&position| match position {
+        Position::Byte(b) | Position::InsideSurrogatePair(b, _) => b < byte_index,
+      })
+    }
+
+    // Length of `s` counted in `IndexUnit`.
+    pub fn len_of(&self, s: &str) -> usize {
+      match self.unit {
+        IndexUnit::Utf8 => s.len(),
+        IndexUnit::Utf16 => s.encode_utf16().count(),
+      }
+    }
+  }
+
+  pub fn char_boundary_error(index: usize, c: char) -> Error {
+    Error::new_with_reason(
+      MagicStringErrorType::MagicStringCharBoundaryError,
+      format!(
+        "Index {} falls inside character '{}' (U+{:04X}).",
+        index, c, c as u32
+      )
+      .as_str(),
+    )
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::IndexTranslator;
+    use crate::magic_string::IndexUnit;
+
+    #[test]
+    fn should_translate_utf16_indices() {
+      // "中" is 3 bytes and 1 UTF-16 code unit, "😀" is 4 bytes and 2 UTF-16 code units
+      let translator = IndexTranslator::new("a中😀b", IndexUnit::Utf16);
+
+      assert_eq!(translator.len, 5);
+      assert_eq!(translator.to_byte_index(0), Ok(0));
+      assert_eq!(translator.to_byte_index(1), Ok(1));
+      assert_eq!(translator.to_byte_index(2), Ok(4));
+      assert_eq!(
+        translator.to_byte_index(3).unwrap_err().reason,
+        Some("Index 3 falls inside character '😀' (U+1F600).".to_owned())
+      );
+      assert_eq!(translator.to_byte_index(4), Ok(8));
+      assert_eq!(translator.to_byte_index(5), Ok(9));
+      assert!(translator.to_byte_index(6).is_err());
+
+      assert_eq!(translator.normalize(-1), Ok(8));
+      assert!(translator.normalize(-6).is_err());
+
+      assert_eq!(translator.byte_to_index(0), 0);
+      assert_eq!(translator.byte_to_index(4), 2);
+      assert_eq!(translator.byte_to_index(8), 4);
+      assert_eq!(translator.byte_to_index(9), 5);
+      assert_eq!(translator.len_of("中😀"), 3);
+    }
+
+    #[test]
+    fn should_keep_utf8_indices() {
+      let translator = IndexTranslator::new("a中😀b", IndexUnit::Utf8);
+
+      assert_eq!(translator.len, 9);
+      assert_eq!(translator.to_byte_index(4), Ok(4));
+      assert_eq!(translator.normalize(-1), Ok(8));
     }
   }
 }
 
 pub mod trim {
+  use std::collections::HashSet;
+
   use regex::Regex;
 
+  #[cfg(test)]
   use crate::Result;
 
-  pub fn trim_start_regexp<'a>(s: &'a str, reg_pat: &'a str) -> Result<&'a str> {
-    if s.is_empty() {
-      return Ok(s);
+  // `pattern` repeated and anchored at the start (`^(?:pattern)+`) or the end (`(?:pattern)+$`) of the whole string.
+  // Only the anchored regexes of the sides to be trimmed are compiled.
+  pub struct TrimRegexp {
+    start: Option<Regex>,
+    end: Option<Regex>,
+  }
+
+  impl TrimRegexp {
+    pub fn new(pattern: &str, start: bool, end: bool) -> crate::Result<Self> {
+      Ok(Self {
+        start: match start {
+          true => Some(Regex::new(format!("^(?:{})+", pattern).as_str())?),
+          false => None,
+        },
+        end: match end {
+          true => Some(Regex::new(format!("(?:{})+$", pattern).as_str())?),
+          false => None,
+        },
+      })
     }
 
-    let matcher = Regex::new(reg_pat)?;
-    let chars = s.chars().collect::<Vec<_>>();
+    pub fn trim_start<'a>(&self, s: &'a str) -> &'a str {
+      match self.start.as_ref().and_then(|start| start.find(s)) {
+        Some(m) => &s[m.end()..],
+        None => s,
+      }
+    }
 
+    pub fn trim_end<'a>(&self, s: &'a str) -> &'a str {
+      match self.end.as_ref().and_then(|end| end.find(s)) {
+        Some(m) => &s[..m.start()],
+        None => s,
+      }
+    }
+  }
+
+  // Trims consecutive matches of a precompiled `matcher` from the start of `s`, each match has to start where the previous one ends.
+  pub fn trim_start_regexp<'a>(s: &'a str, matcher: &Regex) -> &'a str {
     let mut pos = 0;
 
-    while pos < s.len() {
-      let c = chars.get(pos).unwrap();
-      if !matcher.is_match(c.to_string().as_str()) {
+    while let Some(m) = matcher.find_at(s, pos) {
+      // empty matches would never advance
+      if m.start() != pos || m.is_empty() {
         break;
       }
-      pos += 1;
+      pos = m.end();
     }
 
-    Ok(&s[pos..])
+    &s[pos..]
   }
 
-  pub fn trim_end_regexp<'a>(s: &'a str, reg_pat: &'a str) -> Result<&'a str> {
-    if s.is_empty() {
-      return Ok(s);
-    }
-
-    let matcher = Regex::new(reg_pat)?;
-    let chars = s.chars().collect::<Vec<_>>();
-
-    let mut pos = (s.len() - 1) as i32;
+  // Trims consecutive matches of a precompiled `matcher` which reach the end of `s`, each match has to start where the previous one ends.
+  // The match starting at a position is the one `Regex::find_at` reports there, so overlapping matches are taken into account.
+  pub fn trim_end_regexp<'a>(s: &'a str, matcher: &Regex) -> &'a str {
+    let mut matches = vec![];
+    let mut pos = 0;
 
-    while pos >= 0 {
-      let c = chars.get(pos as usize).unwrap();
-      if !matcher.is_match(c.to_string().as_str()) {
+    while let Some(m) = matcher.find_at(s, pos) {
+      if !m.is_empty() {
+        matches.push((m.start(), m.end()));
+      }
+      // search again from the next char, which finds the matches overlapping this one
+      pos = m.start() + s[m.start()..].chars().next().map_or(1, char::len_utf8);
+      if pos > s.len() {
         break;
       }
-      pos -= 1;
     }
 
-    Ok(&s[..(pos + 1) as usize])
+    // walk back from the end, a match can be trimmed if it ends where another trimmable match starts
+    let mut trimmable = HashSet::from([s.len()]);
+    let mut trim_from = s.len();
+    for (start, end) in matches.into_iter().rev() {
+      if trimmable.contains(&end) {
+        trimmable.insert(start);
+        trim_from = start;
+      }
+    }
+
+    &s[..trim_from]
+  }
+
+  // Trims with `pattern` as a string and as a precompiled `Regex`, which should agree on plain patterns.
+  #[cfg(test)]
+  fn trim_start_pattern<'a>(s: &'a str, pattern: &str) -> Result<&'a str> {
+    let trimmed = TrimRegexp::new(pattern, true, false)?.trim_start(s);
+    assert_eq!(trim_start_regexp(s, &Regex::new(pattern)?), trimmed);
+
+    Ok(trimmed)
+  }
+
+  #[cfg(test)]
+  fn trim_end_pattern<'a>(s: &'a str, pattern: &str) -> Result<&'a str> {
+    let trimmed = TrimRegexp::new(pattern, false, true)?.trim_end(s);
+    assert_eq!(trim_end_regexp(s, &Regex::new(pattern)?), trimmed);
+
+    Ok(trimmed)
   }
 
   #[test]
   fn should_trim_start() -> Result {
-    assert_eq!(trim_start_regexp("  abc  ", "\\s")?, "abc  ");
-    assert_eq!(trim_start_regexp("\t\t\tabc\t\t", "\\t")?, "abc\t\t");
-    assert_eq!(trim_start_regexp("\n\nabc\t\t", "\n")?, "abc\t\t");
-    assert_eq!(trim_start_regexp("\n\n\n", "\n")?, "");
+    assert_eq!(trim_start_pattern("  abc  ", "\\s")?, "abc  ");
+    assert_eq!(trim_start_pattern("\t\t\tabc\t\t", "\\t")?, "abc\t\t");
+    assert_eq!(trim_start_pattern("\n\nabc\t\t", "\n")?, "abc\t\t");
+    assert_eq!(trim_start_pattern("\n\n\n", "\n")?, "");
 
     Ok(())
   }
 
   #[test]
   fn should_trim_end() -> Result {
-    assert_eq!(trim_end_regexp("  abc  ", "\\s")?, "  abc");
-    assert_eq!(trim_end_regexp("\t\t\tabc\t\t", "\\t")?, "\t\t\tabc");
-    assert_eq!(trim_end_regexp("\t\tabc\n\n", "\n")?, "\t\tabc");
-    assert_eq!(trim_end_regexp("\n\n\n", "\n")?, "");
+    assert_eq!(trim_end_pattern("  abc  ", "\\s")?, "  abc");
+    assert_eq!(trim_end_pattern("\t\t\tabc\t\t", "\\t")?, "\t\t\tabc");
+    assert_eq!(trim_end_pattern("\t\tabc\n\n", "\n")?, "\t\tabc");
+    assert_eq!(trim_end_pattern("\n\n\n", "\n")?, "");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_trim_non_ascii_contents() -> Result {
+    assert_eq!(trim_start_pattern("\u{3000} 中文 ", "\\s")?, "中文 ");
+    assert_eq!(trim_end_pattern(" 中文\u{3000} ", "\\s")?, " 中文");
+    assert_eq!(trim_end_pattern("中文😀😀", "😀")?, "中文");
+    assert_eq!(trim_start_pattern("中文中文中", "中文")?, "中");
 
     Ok(())
   }
 
   #[test]
   fn should_not_trim_unrelated_contents() -> Result {
-    assert_eq!(trim_start_regexp("\\s\\sabc", "\\s")?, "\\s\\sabc");
-    assert_eq!(trim_end_regexp("abc\\t\\t", "\\t")?, "abc\\t\\t");
+    assert_eq!(trim_start_pattern("\\s\\sabc", "\\s")?, "\\s\\sabc");
+    assert_eq!(trim_end_pattern("abc\\t\\t", "\\t")?, "abc\\t\\t");
+    assert_eq!(trim_start_pattern("abc", "\\s*")?, "abc");
+    assert_eq!(trim_end_pattern("abc", "\\s*")?, "abc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_trim_multi_character_patterns() -> Result {
+    assert_eq!(
+      trim_start_pattern("\r\n\r\n\rabc\r\n", "\r\n")?,
+      "\rabc\r\n"
+    );
+    assert_eq!(trim_end_pattern("abc\n\r\n\r\n", "\r\n")?, "abc\n");
+    assert_eq!(trim_start_pattern(" ; ;abc ;", "\\s*;")?, "abc ;");
+    assert_eq!(trim_end_pattern("abc; ; ;", "\\s*;")?, "abc");
+    assert_eq!(trim_end_pattern("abc;x", "\\s*;")?, "abc;x");
+    assert_eq!(trim_start_pattern("\t\nabc", "\t|\\s")?, "abc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_keep_the_flags_of_precompiled_regexes() -> Result {
+    let matcher = regex::RegexBuilder::new("x")
+      .case_insensitive(true)
+      .build()?;
+
+    assert_eq!(trim_start_regexp("XxXabcXX", &matcher), "abcXX");
+    assert_eq!(trim_end_regexp("XXabcxXx", &matcher), "XXabc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_trim_overlapping_and_odd_length_repetitions() -> Result {
+    assert_eq!(trim_start_pattern("aaax", "aa")?, "ax");
+    assert_eq!(trim_end_pattern("xaaa", "aa")?, "xa");
+    assert_eq!(trim_start_pattern("aaaaa", "aa")?, "a");
+    assert_eq!(trim_end_pattern("aaaaa", "aa")?, "a");
+    assert_eq!(trim_end_pattern("abababa", "aba")?, "abab");
+    assert_eq!(trim_start_pattern("abababa", "aba")?, "baba");
+    assert_eq!(trim_end_pattern("x;;;", ";;|;")?, "x");
 
     Ok(())
   }
 }
 
-use crate::{Error, MagicStringErrorType, Result};
+pub mod indent {
+  /// Guesses the indentation of `s` by looking at the leading whitespace of each line.
+  /// Returns `None` if there's no indented line at all.
+  pub fn guess_indent(s: &str) -> Option<String> {
+    let lines = s.split('\n');
+
+    let mut tabbed = 0;
+    let mut spaced = 0;
+    let mut min_spaces = usize::MAX;
+
+    for line in lines {
+      if line.starts_with('\t') {
+        tabbed += 1;
+        continue;
+      }
+
+      let spaces = line.len() - line.trim_start_matches(' ').len();
+      if spaces >= 2 {
+        spaced += 1;
+        min_spaces = min_spaces.min(spaces);
+      }
+    }
+
+    if tabbed == 0 && spaced == 0 {
+      return None;
+    }
+
+    if tabbed >= spaced {
+      return Some(String::from("\t"));
+    }
 
-pub fn normalize_index(s: &str, index: i64) -> Result<usize> {
-  let len = s.len() as i64;
+    Some(" ".repeat(min_spaces))
+  }
 
-  let index = if index < 0 { index + len } else { index };
+  /// Prefixes every line of `s` with `indent_str`, skipping empty lines.
+  /// `should_indent` carries the state over between calls, i.e. whether the next non-linebreak character starts a new line.
+  pub fn indent_lines(s: &str, indent_str: &str, should_indent: &mut bool) -> String {
+    let mut result = String::with_capacity(s.len());
+
+    for c in s.chars() {
+      if c == '\n' {
+        *should_indent = true;
+      } else if c != '\r' && *should_indent {
+        *should_indent = false;
+        result.push_str(indent_str);
+      }
+      result.push(c);
+    }
 
-  if index < 0 || index > len {
-    return Err(Error::new_with_reason(
-      MagicStringErrorType::MagicStringOutOfRangeError,
-      "index out of range",
-    ));
+    result
   }
 
-  Ok(index as usize)
+  #[test]
+  fn should_guess_indent() {
+    assert_eq!(guess_indent("abc\ndef"), None);
+    assert_eq!(guess_indent("abc\n\tdef\n  ghi"), Some("\t".to_owned()));
+    assert_eq!(
+      guess_indent("abc\n    def\n  ghi\n   jkl"),
+      Some("  ".to_owned())
+    );
+    assert_eq!(guess_indent("abc\n def"), None);
+  }
+
+  #[test]
+  fn should_indent_lines() {
+    let mut should_indent = true;
+    assert_eq!(
+      indent_lines("a\n\nb", "  ", &mut should_indent),
+      "  a\n\n  b"
+    );
+    assert!(!should_indent);
+
+    assert_eq!(indent_lines("c\n", "  ", &mut should_indent), "c\n");
+    assert!(should_indent);
+
+    assert_eq!(indent_lines("\r\nd", "\t", &mut should_indent), "\r\n\td");
+  }
+}
+
+/// Returns the path of `to` relative to the directory of `from`.
+pub fn get_relative_path(from: &str, to: &str) -> String {
+  let mut from_parts = from.split(['/', '\\']).collect::<Vec<_>>();
+  let mut to_parts = to.split(['/', '\\']).collect::<Vec<_>>();
+
+  // get dirname
+  from_parts.pop();
+
+  let common = from_parts
+    .iter()
+    .zip(to_parts.iter())
+    .take_while(|(from, to)| from == to)
+    .count();
+
+  from_parts.drain(..common);
+  to_parts.drain(..common);
+
+  from_parts
+    .iter()
+    .map(|_| "..")
+    .chain(to_parts)
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+#[test]
+fn should_get_relative_path() {
+  assert_eq!(get_relative_path("bundle.js", "src/a.js"), "src/a.js");
+  assert_eq!(
+    get_relative_path("dist/bundle.js", "src/a.js"),
+    "../src/a.js"
+  );
+  assert_eq!(get_relative_path("dist/bundle.js", "dist/a.js"), "a.js");
+  assert_eq!(
+    get_relative_path("dist\\js\\bundle.js", "src\\a.js"),
+    "../../src/a.js"
+  );
 }
@@ -0,0 +1,263 @@
+#[cfg(test)]
+mod bundle {
+  use magic_string::{
+    Bundle, BundleOptions, GenerateDecodedMapOptions, Hires, MagicString, MagicStringErrorType,
+    OverwriteOptions, Result,
+  };
+
+  #[test]
+  fn should_separate_with_a_new_line_by_default() -> Result {
+    let mut b = Bundle::new(BundleOptions::default());
+
+    b.add_source(None, MagicString::new("abc"))?;
+    b.add_source(None, MagicString::new("def"))?;
+
+    assert_eq!(b.to_string(), "abc\ndef");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_accept_a_custom_separator() -> Result {
+    let mut b = Bundle::new(BundleOptions {
+      separator: Some("==".to_owned()),
+      ..BundleOptions::default()
+    });
+
+    b.add_source(None, MagicString::new("abc"))?;
+    b.add_source(None, MagicString::new("def"))?;
+
+    assert_eq!(b.to_string(), "abc==def");
+
+    let mut b = Bundle::new(BundleOptions {
+      separator: Some("".to_owned()),
+      ..BundleOptions::default()
+    });
+
+    b.add_source(None, MagicString::new("abc"))?;
+    b.add_source(None, MagicString::new("def"))?;
+
+    assert_eq!(b.to_string(), "abcdef");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_prepend_and_append_content() -> Result {
+    let mut b = Bundle::new(BundleOptions {
+      intro: Some("// intro\n".to_owned()),
+      ..BundleOptions::default()
+    });
+
+    b.add_source(None, MagicString::new("abc"))?;
+    b.prepend("/* banner */\n")?;
+    b.append("\n// outro", None)?;
+    b.append("// footer", Some("\n"))?;
+
+    assert_eq!(
+      b.to_string(),
+      "/* banner */\n// intro\nabc\n// outro\n// footer"
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_include_edits_of_sources() -> Result {
+    let mut s1 = MagicString::new("var answer = 42;");
+    s1.overwrite(4, 10, "number", OverwriteOptions::default())?;
+    let mut s2 = MagicString::new("console.log( answer );");
+    s2.overwrite(13, 19, "number", OverwriteOptions::default())?;
+
+    let mut b = Bundle::new(BundleOptions::default());
+    b.add_source(Some("foo.js"), s1)?;
+    b.add_source(Some("bar.js"), s2)?;
+
+    assert_eq!(b.to_string(), "var number = 42;\nconsole.log( number );");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_for_same_filename_with_different_contents() -> Result {
+    let mut b = Bundle::new(BundleOptions::default());
+
+    b.add_source(Some("foo.js"), MagicString::new("abc"))?;
+    b.add_source(Some("foo.js"), MagicString::new("abc"))?;
+
+    let err = b
+      .add_source(Some("foo.js"), MagicString::new("def"))
+      .unwrap_err();
+    assert_eq!(
+      err.error_type,
+      MagicStringErrorType::MagicStringIllegalSourceError
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_generate_a_sourcemap() -> Result {
+    let mut b = Bundle::new(BundleOptions::default());
+
+    b.add_source(Some("foo.js"), MagicString::new("var answer = 42;"))?;
+    b.add_source(Some("bar.js"), MagicString::new("console.log( answer );"))?;
+
+    let map = b.generate_map(GenerateDecodedMapOptions {
+      file: Some("bundle.js".to_owned()),
+      include_content: true,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    assert_eq!(map.version, 3);
+    assert_eq!(map.file, Some("bundle.js".to_owned()));
+    assert_eq!(
+      map.sources,
+      vec![Some("foo.js".to_owned()), Some("bar.js".to_owned())]
+    );
+    assert_eq!(
+      map.sources_content,
+      vec![
+        Some("var answer = 42;".to_owned()),
+        Some("console.log( answer );".to_owned())
+      ]
+    );
+    assert_eq!(map.mappings, "AAAA;ACAA");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_generate_a_hires_sourcemap() -> Result {
+    let mut b = Bundle::new(BundleOptions::default());
+
+    b.add_source(Some("foo.js"), MagicString::new("ab"))?;
+    b.add_source(Some("bar.js"), MagicString::new("cd"))?;
+
+    let map = b.generate_decoded_map(GenerateDecodedMapOptions {
+      hires: Hires::Full,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    assert_eq!(
+      map.mappings,
+      vec![
+        vec![vec![0, 0, 0, 0], vec![1, 0, 0, 1]],
+        vec![vec![0, 1, 0, -1], vec![1, 0, 0, 1]]
+      ]
+    );
+    assert_eq!(map.sources_content, vec![None, None]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_deduplicate_sources() -> Result {
+    let mut b = Bundle::new(BundleOptions {
+      separator: Some("\n;\n".to_owned()),
+      ..BundleOptions::default()
+    });
+
+    b.add_source(Some("foo.js"), MagicString::new("foo()"))?;
+    b.add_source(Some("bar.js"), MagicString::new("bar()"))?;
+    b.add_source(Some("foo.js"), MagicString::new("foo()"))?;
+
+    let map = b.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(
+      map.sources,
+      vec![Some("foo.js".to_owned()), Some("bar.js".to_owned())]
+    );
+    assert_eq!(
+      map.mappings,
+      vec![
+        vec![vec![0, 0, 0, 0]],
+        vec![],
+        vec![vec![0, 1, 0, 0]],
+        vec![],
+        vec![vec![0, -1, 0, 0]],
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_map_sources_without_filename() -> Result {
+    let mut b = Bundle::new(BundleOptions::default());
+
+    b.add_source(None, MagicString::new("// comment"))?;
+    b.add_source(Some("foo.js"), MagicString::new("foo()"))?;
+    b.append("\n//# sourceMappingURL=bundle.js.map", None)?;
+
+    let map = b.generate_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(map.sources, vec![Some("foo.js".to_owned())]);
+    assert_eq!(map.mappings, ";AAAA;");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_make_sources_relative_to_file() -> Result {
+    let mut b = Bundle::new(BundleOptions::default());
+
+    b.add_source(Some("src/foo.js"), MagicString::new("foo()"))?;
+    b.add_source(Some("lib/bar.js"), MagicString::new("bar()"))?;
+
+    let map = b.generate_map(GenerateDecodedMapOptions {
+      file: Some("dist/bundle.js".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    assert_eq!(map.file, Some("bundle.js".to_owned()));
+    assert_eq!(
+      map.sources,
+      vec![
+        Some("../src/foo.js".to_owned()),
+        Some("../lib/bar.js".to_owned())
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_merge_names_of_sources() -> Result {
+    let options = OverwriteOptions {
+      store_name: true,
+      ..OverwriteOptions::default()
+    };
+
+    let mut s1 = MagicString::new("var answer = 42;");
+    s1.overwrite(4, 10, "a", options.to_owned())?;
+    let mut s2 = MagicString::new("console.log(question, answer);");
+    s2.overwrite(12, 20, "q", options.to_owned())?;
+    s2.overwrite(22, 28, "a", options)?;
+
+    let mut b = Bundle::new(BundleOptions::default());
+    b.add_source(Some("foo.js"), s1)?;
+    b.add_source(Some("bar.js"), s2)?;
+
+    assert_eq!(b.to_string(), "var a = 42;\nconsole.log(q, a);");
+
+    let map = b.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(map.names, vec!["answer".to_owned(), "question".to_owned()]);
+    assert_eq!(
+      map.mappings,
+      vec![
+        vec![vec![0, 0, 0, 0], vec![4, 0, 0, 4, 0], vec![1, 0, 0, 6]],
+        vec![
+          vec![0, 1, 0, -10],
+          vec![12, 0, 0, 12, 1],
+          vec![1, 0, 0, 8],
+          vec![2, 0, 0, 2, -1],
+          vec![1, 0, 0, 6]
+        ],
+      ]
+    );
+
+    Ok(())
+  }
+}
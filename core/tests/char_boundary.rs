@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod char_boundary {
+  use magic_string::{
+    Error, IndentOptions, MagicString, MagicStringErrorType, OverwriteOptions, Result,
+  };
+
+  fn char_boundary_error(reason: &str) -> Error {
+    Error::new_with_reason(MagicStringErrorType::MagicStringCharBoundaryError, reason)
+  }
+
+  #[test]
+  fn should_not_split_inside_a_multibyte_character() -> Result {
+    // "中" occupies bytes 1..4
+    let mut s = MagicString::new("a中b");
+
+    assert_eq!(
+      s.overwrite(2, 4, "x", OverwriteOptions::default())
+        .unwrap_err(),
+      char_boundary_error("Index 2 falls inside character '中' (U+4E2D).")
+    );
+    assert_eq!(
+      s.append_left(3, "x").unwrap_err(),
+      char_boundary_error("Index 3 falls inside character '中' (U+4E2D).")
+    );
+    assert!(s.remove(0, 2).is_err());
+    assert!(s.prepend_right(2, "x").is_err());
+    assert!(s._move(0, 2, 5).is_err());
+
+    assert_eq!(s.to_string(), "a中b");
+
+    s.overwrite(1, 4, "x", OverwriteOptions::default())?;
+    assert_eq!(s.to_string(), "axb");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_name_astral_characters() -> Result {
+    let mut s = MagicString::new("😀");
+
+    assert_eq!(
+      s.remove(0, 2).unwrap_err(),
+      char_boundary_error("Index 2 falls inside character '😀' (U+1F600).")
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_validate_slice_and_sourcemap_location_indices() -> Result {
+    let mut s = MagicString::new("a中b");
+
+    assert!(s.slice(0, 2).is_err());
+    assert!(s.snip(2, 5).is_err());
+    assert!(s.add_sourcemap_location(2).is_err());
+
+    assert_eq!(s.slice(1, 4)?, "中");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_panic_on_out_of_range_indices() -> Result {
+    let mut s = MagicString::new("a中b");
+
+    assert!(s.append_left(6, "x").is_err());
+    assert!(s.prepend_left(u32::MAX, "x").is_err());
+    assert!(s.add_sourcemap_location(6).is_err());
+    assert!(s.slice(0, 6).is_err());
+    assert!(s.slice(4, 1).is_err());
+    assert!(s.slice(-1, 0).is_err());
+    assert!(s
+      .indent(
+        Some("  "),
+        IndentOptions {
+          exclude: Some(vec![vec![0, 6]]),
+          ..IndentOptions::default()
+        }
+      )
+      .is_err());
+
+    assert!(s._move(5, 5, 0).is_err());
+    assert!(s._move(0, 0, 4).is_err());
+
+    assert_eq!(s.to_string(), "a中b");
+
+    let mut s = MagicString::new("abcdefghijkl");
+
+    let err = s._move(12, 12, 0).unwrap_err();
+    assert_eq!(
+      err.error_type,
+      MagicStringErrorType::MagicStringOutOfRangeError
+    );
+    assert!(s._move(0, 0, 5).is_err());
+
+    assert_eq!(s.to_string(), "abcdefghijkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_trim_non_ascii_whitespaces() -> Result {
+    let mut s = MagicString::new("\u{3000}中文\u{3000}");
+    s.trim(None)?;
+
+    assert_eq!(s.to_string(), "中文");
+
+    Ok(())
+  }
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod clone {
+  use magic_string::{GenerateDecodedMapOptions, MagicString, OverwriteOptions, Result};
+
+  #[test]
+  fn should_clone_a_magic_string() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.overwrite(3, 9, "XYZ", OverwriteOptions::default())?;
+    let c = s.clone();
+
+    assert_eq!(c.to_string(), "abcXYZjkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_clone_intro_and_outro() -> Result {
+    let mut s = MagicString::new("defghi");
+
+    s.prepend("abc")?;
+    s.append("jkl")?;
+    let c = s.clone();
+
+    assert_eq!(c.to_string(), s.to_string());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_leak_edits_of_the_clone_back_to_the_original() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.append_left(3, "(")?;
+
+    let mut c = s.clone();
+
+    c.overwrite(0, 3, "ABC", OverwriteOptions::default())?;
+    c.append_left(3, ")")?;
+    c.remove(9, 12)?;
+    c._move(3, 6, 9)?;
+    c.prepend(">")?;
+
+    assert_eq!(c.to_string(), ">ABC)ghidef");
+    assert_eq!(s.to_string(), "abc(defghijkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_leak_edits_of_the_original_to_the_clone() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    let mut c = s.clone();
+
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+    s._move(0, 3, 12)?;
+
+    assert_eq!(s.to_string(), "DEFghijklabc");
+    assert_eq!(c.to_string(), "abcdefghijkl");
+
+    // the original has been split at index 3 and 6, which shouldn't affect the clone
+    c.overwrite(2, 7, "X", OverwriteOptions::default())?;
+
+    assert_eq!(c.to_string(), "abXhijkl");
+    assert_eq!(s.to_string(), "DEFghijklabc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_generate_the_same_sourcemap() -> Result {
+    let mut s = MagicString::new("abc\ndef\nghi");
+
+    s.overwrite(4, 7, "DEF", OverwriteOptions::default())?;
+    s._move(8, 11, 0)?;
+
+    let c = s.clone();
+
+    assert_eq!(
+      c.generate_map(GenerateDecodedMapOptions::default())?
+        .to_string()?,
+      s.generate_map(GenerateDecodedMapOptions::default())?
+        .to_string()?
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_clone_sourcemap_locations() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.add_sourcemap_location(3)?;
+
+    let mut c = s.clone();
+    c.add_sourcemap_location(6)?;
+
+    assert_eq!(
+      s.generate_map(GenerateDecodedMapOptions::default())?
+        .mappings,
+      "AAAA,GAAG"
+    );
+    assert_eq!(
+      c.generate_map(GenerateDecodedMapOptions::default())?
+        .mappings,
+      "AAAA,GAAG,GAAG"
+    );
+
+    Ok(())
+  }
+}
@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod finalize {
+  use magic_string::{
+    Bundle, BundleOptions, GenerateDecodedMapOptions, MagicString, Result, SourceMap,
+    SourceMappingUrlStyle,
+  };
+
+  #[test]
+  fn should_append_an_inline_sourcemap() -> Result {
+    let mut s = MagicString::new("abc");
+    s.prepend("// header\n")?;
+
+    let (code, map) = s.finalize(
+      GenerateDecodedMapOptions::default(),
+      SourceMappingUrlStyle::Inline,
+    )?;
+
+    assert_eq!(
+      code,
+      format!("// header\nabc\n//# sourceMappingURL={}", map.to_url()?)
+    );
+    assert_eq!(map.mappings, ";AAAA");
+
+    let url = code.rsplit("sourceMappingURL=").next().unwrap();
+    assert_eq!(SourceMap::from_url(url)?.to_string()?, map.to_string()?);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_append_an_external_sourcemap() -> Result {
+    let mut s = MagicString::new("abc");
+    s.prepend("// header\n")?;
+
+    let (code, map) = s.finalize(
+      GenerateDecodedMapOptions {
+        file: Some("index.js".to_owned()),
+        ..GenerateDecodedMapOptions::default()
+      },
+      SourceMappingUrlStyle::External("index.js.map".to_owned()),
+    )?;
+
+    assert_eq!(code, "// header\nabc\n//# sourceMappingURL=index.js.map");
+    assert_eq!(map.file, Some("index.js".to_owned()));
+    // the original string is left untouched
+    assert_eq!(s.to_string(), "// header\nabc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_append_a_comment_for_hidden_sourcemaps() -> Result {
+    let mut s = MagicString::new("abc");
+    s.prepend("// header\n")?;
+
+    let (code, map) = s.finalize(
+      GenerateDecodedMapOptions::default(),
+      SourceMappingUrlStyle::Hidden,
+    )?;
+
+    assert_eq!(code, "// header\nabc");
+    assert_eq!(map.mappings, ";AAAA");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_append_css_comments() -> Result {
+    let mut s = MagicString::new("a { color: red; }\n");
+    s.prepend("@charset \"utf-8\";\n")?;
+
+    let (code, _) = s.finalize(
+      GenerateDecodedMapOptions::default(),
+      SourceMappingUrlStyle::CssComment(Some("index.css.map".to_owned())),
+    )?;
+    assert_eq!(
+      code,
+      "@charset \"utf-8\";\na { color: red; }\n/*# sourceMappingURL=index.css.map */"
+    );
+
+    let (code, map) = s.finalize(
+      GenerateDecodedMapOptions::default(),
+      SourceMappingUrlStyle::CssComment(None),
+    )?;
+    assert!(code.ends_with(format!("\n/*# sourceMappingURL={} */", map.to_url()?).as_str()));
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_append_the_debug_id() -> Result {
+    let mut s = MagicString::new("abc");
+    s.prepend("// header\n")?;
+
+    let options = GenerateDecodedMapOptions {
+      debug_id: Some("85314830-023f-4cf1-a267-535f4e37bb17".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    };
+
+    let (code, map) = s.finalize(
+      options.clone(),
+      SourceMappingUrlStyle::External("index.js.map".to_owned()),
+    )?;
+    assert_eq!(
+      code,
+      "// header\nabc\n//# debugId=85314830-023f-4cf1-a267-535f4e37bb17\n//# sourceMappingURL=index.js.map"
+    );
+    assert_eq!(map.debug_id, options.debug_id);
+
+    let (code, _) = s.finalize(options.clone(), SourceMappingUrlStyle::Hidden)?;
+    assert_eq!(
+      code,
+      "// header\nabc\n//# debugId=85314830-023f-4cf1-a267-535f4e37bb17"
+    );
+
+    let (code, _) = s.finalize(
+      options,
+      SourceMappingUrlStyle::CssComment(Some("a.map".to_owned())),
+    )?;
+    assert_eq!(
+      code,
+      "// header\nabc\n/*# debugId=85314830-023f-4cf1-a267-535f4e37bb17 */\n/*# sourceMappingURL=a.map */"
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_finalize_bundles() -> Result {
+    let mut bundle = Bundle::new(BundleOptions::default());
+    bundle.add_source(Some("a.js"), MagicString::new("const a = 1"))?;
+    bundle.add_source(Some("b.js"), MagicString::new("const b = 2"))?;
+
+    let (code, map) = bundle.finalize(
+      GenerateDecodedMapOptions::default(),
+      SourceMappingUrlStyle::External("bundle.js.map".to_owned()),
+    )?;
+
+    assert_eq!(
+      code,
+      "const a = 1\nconst b = 2\n//# sourceMappingURL=bundle.js.map"
+    );
+    assert_eq!(
+      map.to_string()?,
+      bundle
+        .generate_map(GenerateDecodedMapOptions::default())?
+        .to_string()?
+    );
+
+    Ok(())
+  }
+}
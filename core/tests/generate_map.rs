@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod sourcemap {
-  use magic_string::{GenerateDecodedMapOptions, MagicString, OverwriteOptions, Result};
+  use magic_string::{
+    ColumnUnit, GenerateDecodedMapOptions, Hires, MagicString, OverwriteOptions, Result,
+  };
 
   #[test]
   fn should_generate_a_sourcemap() -> Result {
@@ -12,7 +14,8 @@ mod sourcemap {
       source_root: Some("./".to_owned()),
       source: Some("input.md".to_owned()),
       include_content: true,
-      hires: false,
+      hires: Hires::Off,
+      ..GenerateDecodedMapOptions::default()
     })?;
 
     assert_eq!(map.version, 3);
@@ -30,10 +33,10 @@ mod sourcemap {
       source_root: Some("./".to_owned()),
       source: Some("input.md".to_owned()),
       include_content: true,
-      hires: true,
+      hires: Hires::Full,
+      ..GenerateDecodedMapOptions::default()
     })?;
 
-    println!("{}{}", map.to_string()?, s.to_string());
     assert_eq!(map.mappings, "AAAA,CAAC,CAAC,CAAO,CAAC,CAAC".to_owned());
 
     Ok(())
@@ -64,11 +67,10 @@ mod sourcemap {
 
     let map = s.generate_map(GenerateDecodedMapOptions {
       include_content: true,
-      hires: true,
+      hires: Hires::Full,
       ..GenerateDecodedMapOptions::default()
     })?;
 
-    println!("{}", s.to_string());
     assert_eq!(map.mappings, ";AAAA,CAAC;AACD");
 
     Ok(())
@@ -123,6 +125,7 @@ mod sourcemap {
   }
 
   #[test]
+  #[allow(clippy::get_first)]
   fn should_generate_one_segment_per_replacement() -> Result {
     let mut s = MagicString::new("var answer = 42");
 
@@ -204,4 +207,355 @@ mod sourcemap {
 
     Ok(())
   }
+
+  #[test]
+  fn should_add_segments_at_sourcemap_locations() -> Result {
+    let mut s = MagicString::new("abc\ndef\nghi");
+
+    s.add_sourcemap_location(2)?;
+    s.add_sourcemap_location(9)?;
+    s.prepend("// ")?;
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(
+      decoded_map.mappings,
+      vec![
+        vec![vec![3, 0, 0, 0], vec![2, 0, 0, 2]],
+        vec![vec![0, 0, 1, -2]],
+        vec![vec![0, 0, 1, 0], vec![1, 0, 0, 1]],
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_ignore_sourcemap_locations_inside_edited_chunks() -> Result {
+    let mut s = MagicString::new("var answer = 42");
+
+    s.add_sourcemap_location(5)?;
+    s.add_sourcemap_location(13)?;
+    s.overwrite(4, 10, "number", OverwriteOptions::default())?;
+
+    let map = s.generate_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(map.mappings, "AAAA,IAAI,MAAM,GAAG");
+    assert!(s.add_sourcemap_location(16).is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_store_names_of_overwritten_content() -> Result {
+    let mut s = MagicString::new("function Foo () {}\nnew Foo();\nFoo.bar = Baz;");
+
+    let options = OverwriteOptions {
+      store_name: true,
+      ..OverwriteOptions::default()
+    };
+
+    s.overwrite(9, 12, "Bar", options.to_owned())?;
+    s.overwrite(40, 43, "Qux", options.to_owned())?;
+    s.overwrite(23, 26, "Bar", options)?;
+    s.overwrite(30, 33, "Bar", OverwriteOptions::default())?;
+
+    assert_eq!(
+      s.to_string(),
+      "function Bar () {}\nnew Bar();\nBar.bar = Qux;"
+    );
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(decoded_map.names, vec!["Foo".to_owned(), "Baz".to_owned()]);
+    assert_eq!(
+      decoded_map.mappings,
+      vec![
+        vec![vec![0, 0, 0, 0], vec![9, 0, 0, 9, 0], vec![3, 0, 0, 3]],
+        vec![vec![0, 0, 1, -12], vec![4, 0, 0, 4, 0], vec![3, 0, 0, 3]],
+        vec![
+          vec![0, 0, 1, -7],
+          vec![3, 0, 0, 3],
+          vec![7, 0, 0, 7, 1],
+          vec![3, 0, 0, 3]
+        ],
+      ]
+    );
+
+    let map = s.generate_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(map.names, vec!["Foo".to_owned(), "Baz".to_owned()]);
+    assert_eq!(
+      map.mappings,
+      "AAAA,SAASA,GAAG;AACZ,IAAIA,GAAG;AACP,GAAG,OAAOC,GAAG"
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_store_names_of_content_overwritten_across_chunks() -> Result {
+    let mut s = MagicString::new("var fooBar = 1;");
+    s.append_left(7, "_")?;
+    s.overwrite(
+      4,
+      10,
+      "baz",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+
+    assert_eq!(s.to_string(), "var baz = 1;");
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(decoded_map.names, vec!["fooBar".to_owned()]);
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![
+        vec![0, 0, 0, 0],
+        vec![4, 0, 0, 4, 0],
+        vec![3, 0, 0, 6]
+      ]]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_store_names_of_failed_overwrites() -> Result {
+    let mut s = MagicString::new("var fooBar = 1;");
+    s._move(7, 10, 0)?;
+
+    assert!(s
+      .overwrite(
+        4,
+        10,
+        "baz",
+        OverwriteOptions {
+          store_name: true,
+          ..OverwriteOptions::default()
+        },
+      )
+      .is_err());
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+
+    assert!(decoded_map.names.is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_count_columns_in_utf16_code_units_for_bmp_characters() -> Result {
+    let mut s = MagicString::new("let s = \"中文\"; foo();");
+    s.overwrite(18, 21, "bar", OverwriteOptions::default())?;
+
+    assert_eq!(s.to_string(), "let s = \"中文\"; bar();");
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![vec![0, 0, 0, 0], vec![14, 0, 0, 14], vec![3, 0, 0, 3]]]
+    );
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions {
+      column_unit: ColumnUnit::Utf8,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![vec![0, 0, 0, 0], vec![18, 0, 0, 18], vec![3, 0, 0, 3]]]
+    );
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions {
+      column_unit: ColumnUnit::Char,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![vec![0, 0, 0, 0], vec![14, 0, 0, 14], vec![3, 0, 0, 3]]]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_count_columns_in_utf16_code_units_for_astral_characters() -> Result {
+    let mut s = MagicString::new("let s = \"😀\"; foo();");
+    s.overwrite(16, 19, "bar", OverwriteOptions::default())?;
+    s.prepend("/* 😀 */ ")?;
+
+    assert_eq!(s.to_string(), "/* 😀 */ let s = \"😀\"; bar();");
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![vec![9, 0, 0, 0], vec![14, 0, 0, 14], vec![3, 0, 0, 3]]]
+    );
+
+    let map = s.generate_map(GenerateDecodedMapOptions::default())?;
+    assert_eq!(map.mappings, "SAAA,cAAc,GAAG");
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions {
+      column_unit: ColumnUnit::Utf8,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![
+        vec![11, 0, 0, 0],
+        vec![16, 0, 0, 16],
+        vec![3, 0, 0, 3]
+      ]]
+    );
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions {
+      column_unit: ColumnUnit::Char,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![vec![8, 0, 0, 0], vec![13, 0, 0, 13], vec![3, 0, 0, 3]]]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_count_columns_in_utf16_code_units_when_hires() -> Result {
+    let mut s = MagicString::new("a😀b\n😀c");
+    s.prepend("中")?;
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions {
+      hires: Hires::Full,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![
+        vec![
+          vec![1, 0, 0, 0],
+          vec![1, 0, 0, 1],
+          vec![2, 0, 0, 2],
+          vec![1, 0, 0, 1]
+        ],
+        vec![vec![0, 0, 1, -4], vec![2, 0, 0, 2]],
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_map_word_boundaries_when_hires_is_boundary() -> Result {
+    let mut s = MagicString::new("foo.bar(baz);\nqux_1 = 中");
+    s.add_sourcemap_location(9)?;
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions {
+      hires: Hires::Boundary,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![
+        vec![
+          vec![0, 0, 0, 0],
+          vec![3, 0, 0, 3],
+          vec![1, 0, 0, 1],
+          vec![3, 0, 0, 3],
+          vec![1, 0, 0, 1],
+          vec![1, 0, 0, 1],
+          vec![2, 0, 0, 2],
+          vec![1, 0, 0, 1],
+          vec![1, 0, 0, 1]
+        ],
+        vec![
+          vec![0, 0, 1, -13],
+          vec![5, 0, 0, 5],
+          vec![1, 0, 0, 1],
+          vec![1, 0, 0, 1],
+          vec![1, 0, 0, 1]
+        ],
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_create_hires_from_bool() -> Result {
+    assert_eq!(Hires::from(true), Hires::Full);
+    assert_eq!(Hires::from(false), Hires::Off);
+    assert_eq!(Hires::default(), Hires::Off);
+
+    let s = MagicString::new("abc");
+    assert_eq!(
+      s.generate_map(GenerateDecodedMapOptions {
+        hires: true.into(),
+        ..GenerateDecodedMapOptions::default()
+      })?
+      .mappings,
+      "AAAA,CAAC,CAAC"
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_encode_the_same_mappings_as_the_decoded_map() -> Result {
+    let mut s = MagicString::new("const foo = 1;\nconst bar = 2;\n\nexport { foo, bar };");
+    s.overwrite(
+      6,
+      9,
+      "baz",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("/* 😀 */\n")?;
+    s.append_left(15, "// inserted\n")?;
+    s.remove(21, 25)?;
+    s._move(0, 15, 51)?;
+    s.add_sourcemap_location(42)?;
+
+    for hires in [Hires::Off, Hires::Boundary, Hires::Full] {
+      let options = GenerateDecodedMapOptions {
+        hires,
+        ..GenerateDecodedMapOptions::default()
+      };
+
+      let map = s.generate_map(options.clone())?;
+      let decoded_map = s.generate_decoded_map(options)?;
+
+      assert_eq!(map.to_decoded_map()?.mappings, decoded_map.mappings);
+      assert_eq!(map.names, decoded_map.names);
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_keep_empty_lines_of_overwritten_content() -> Result {
+    let mut s = MagicString::new("ab");
+    s.overwrite(0, 1, "x\n\ny", OverwriteOptions::default())?;
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![
+        vec![vec![0, 0, 0, 0]],
+        vec![],
+        vec![vec![0, 0, 0, 0], vec![1, 0, 0, 1]]
+      ]
+    );
+
+    let map = s.generate_map(GenerateDecodedMapOptions::default())?;
+    assert_eq!(map.mappings, "AAAA;;AAAA,CAAC");
+
+    Ok(())
+  }
 }
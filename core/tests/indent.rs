@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod indent {
+  use magic_string::{
+    GenerateDecodedMapOptions, Hires, IndentOptions, MagicString, OverwriteOptions, Result,
+  };
+
+  #[test]
+  fn should_indent_each_line() -> Result {
+    let mut s = MagicString::new("abc\ndef\nghi\njkl");
+
+    s.indent(None, IndentOptions::default())?;
+    assert_eq!(s.to_string(), "\tabc\n\tdef\n\tghi\n\tjkl");
+
+    s.indent(None, IndentOptions::default())?;
+    assert_eq!(s.to_string(), "\t\tabc\n\t\tdef\n\t\tghi\n\t\tjkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_indent_with_a_specified_string() -> Result {
+    let mut s = MagicString::new("abc\ndef\nghi\njkl");
+
+    s.indent_with(">>")?;
+    assert_eq!(s.to_string(), ">>abc\n>>def\n>>ghi\n>>jkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_guess_the_indent_string() -> Result {
+    let mut s = MagicString::new("abc\n  def\n    ghi");
+    s.indent(None, IndentOptions::default())?;
+    assert_eq!(s.to_string(), "  abc\n    def\n      ghi");
+
+    let mut s = MagicString::new("abc\n\tdef\n  ghi");
+    s.indent(None, IndentOptions::default())?;
+    assert_eq!(s.to_string(), "\tabc\n\t\tdef\n\t  ghi");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_noop_with_an_empty_string() -> Result {
+    let mut s = MagicString::new("abc\ndef");
+
+    s.indent(Some(""), IndentOptions::default())?;
+    assert_eq!(s.to_string(), "abc\ndef");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_indent_empty_lines() -> Result {
+    let mut s = MagicString::new("abc\n\ndef\r\n\r\nghi\n");
+
+    s.indent_with("  ")?;
+    assert_eq!(s.to_string(), "  abc\n\n  def\r\n\r\n  ghi\n");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_indent_intros_and_outros() -> Result {
+    let mut s = MagicString::new("abc\ndef");
+
+    s.prepend("// intro\n")?;
+    s.append("\n// outro")?;
+    s.append_left(3, "\n// inserted")?;
+
+    s.indent_with("  ")?;
+    assert_eq!(
+      s.to_string(),
+      "  // intro\n  abc\n  // inserted\n  def\n  // outro"
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_indent_overwritten_content() -> Result {
+    let mut s = MagicString::new("abc\ndef\nghi");
+
+    s.overwrite(4, 7, "x\ny", OverwriteOptions::default())?;
+
+    s.indent_with("  ")?;
+    assert_eq!(s.to_string(), "  abc\n  x\n  y\n  ghi");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_exclude_ranges() -> Result {
+    let mut s = MagicString::new("abc\ndef\nghi\njkl");
+
+    s.indent(
+      Some(">>"),
+      IndentOptions {
+        exclude: Some(vec![vec![7, 15]]),
+        ..IndentOptions::default()
+      },
+    )?;
+    assert_eq!(s.to_string(), ">>abc\n>>def\nghi\njkl");
+
+    let mut s = MagicString::new("abc\ndef\nghi\njkl");
+
+    s.indent(
+      Some(">>"),
+      IndentOptions {
+        exclude: Some(vec![vec![0, 4], vec![8, 12]]),
+        ..IndentOptions::default()
+      },
+    )?;
+    assert_eq!(s.to_string(), "abc\n>>def\nghi\n>>jkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_indent_the_first_line_if_indent_start_is_false() -> Result {
+    let mut s = MagicString::new("abc\ndef");
+
+    s.indent(
+      Some("  "),
+      IndentOptions {
+        indent_start: Some(false),
+        ..IndentOptions::default()
+      },
+    )?;
+    assert_eq!(s.to_string(), "abc\n  def");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_for_invalid_exclusions() {
+    let mut s = MagicString::new("abc\ndef");
+
+    assert!(s
+      .indent(
+        None,
+        IndentOptions {
+          exclude: Some(vec![vec![1]]),
+          ..IndentOptions::default()
+        },
+      )
+      .is_err());
+  }
+
+  #[test]
+  fn should_preserve_original_columns_in_sourcemap() -> Result {
+    let mut s = MagicString::new("abc\ndef");
+
+    s.indent_with("  ")?;
+
+    let decoded_map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+    assert_eq!(
+      decoded_map.mappings,
+      vec![vec![vec![2, 0, 0, 0]], vec![vec![2, 0, 1, 0]]]
+    );
+
+    let map = s.generate_map(GenerateDecodedMapOptions {
+      hires: Hires::Full,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(map.mappings, "EAAA,CAAC,CAAC,CAAC;EACH,CAAC,CAAC");
+
+    Ok(())
+  }
+}
@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod original_index_for {
+  use magic_string::{IndexUnit, MagicString, MagicStringOptions, OverwriteOptions, Result};
+
+  #[test]
+  fn should_map_unedited_characters() -> Result {
+    let s = MagicString::new("abcdefghijkl");
+
+    for i in 0..12 {
+      assert_eq!(s.original_index_for(i), Some(i as u32));
+    }
+    assert_eq!(s.original_index_for(12), None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_none_for_inserted_content() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.prepend("<")?;
+    s.append(">")?;
+    s.append_left(3, "[")?;
+    s.prepend_right(3, "(")?;
+
+    assert_eq!(s.to_string(), "<abc[(defghijkl>");
+    assert_eq!(s.original_index_for(0), None);
+    assert_eq!(s.original_index_for(3), Some(2));
+    assert_eq!(s.original_index_for(4), None);
+    assert_eq!(s.original_index_for(5), None);
+    assert_eq!(s.original_index_for(6), Some(3));
+    assert_eq!(s.original_index_for(14), Some(11));
+    assert_eq!(s.original_index_for(15), None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_account_for_removed_and_moved_content() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.remove(0, 3)?;
+    s._move(6, 9, 3)?;
+
+    assert_eq!(s.to_string(), "ghidefjkl");
+    assert_eq!(s.original_index_for(0), Some(6));
+    assert_eq!(s.original_index_for(3), Some(3));
+    assert_eq!(s.original_index_for(6), Some(9));
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_count_utf16_code_units() -> Result {
+    let mut s = MagicString::new_with_options(
+      "😀a中b",
+      MagicStringOptions {
+        index_unit: IndexUnit::Utf16,
+      },
+    );
+    s.prepend("中")?;
+    s.overwrite(3, 4, "😀😀", OverwriteOptions::default())?;
+
+    assert_eq!(s.to_string(), "中😀a😀😀b");
+    assert_eq!(s.original_index_for(0), None);
+    assert_eq!(s.original_index_for(1), Some(0));
+    assert_eq!(s.original_index_for(3), Some(2));
+    assert_eq!(s.original_index_for(6), Some(3));
+    assert_eq!(s.original_index_for(8), Some(4));
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod generated_index_for {
+  use magic_string::{IndexUnit, MagicString, MagicStringOptions, OverwriteOptions, Result};
+
+  #[test]
+  fn should_map_characters_after_inserted_content() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.prepend("<")?;
+    s.append_left(3, "[")?;
+    s.prepend_right(3, "(")?;
+
+    assert_eq!(s.to_string(), "<abc[(defghijkl");
+    assert_eq!(s.generated_index_for(0), Some(1));
+    assert_eq!(s.generated_index_for(3), Some(6));
+    assert_eq!(s.generated_index_for(11), Some(14));
+    assert_eq!(s.generated_index_for(12), None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_account_for_overwritten_removed_and_moved_content() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.remove(0, 3)?;
+    s.overwrite(3, 6, "D", OverwriteOptions::default())?;
+    s._move(6, 9, 3)?;
+
+    assert_eq!(s.to_string(), "ghiDjkl");
+    assert_eq!(s.generated_index_for(1), None);
+    assert_eq!(s.generated_index_for(4), Some(3));
+    assert_eq!(s.generated_index_for(5), Some(3));
+    assert_eq!(s.generated_index_for(7), Some(1));
+    assert_eq!(s.generated_index_for(9), Some(4));
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_map_overwritten_ranges_spanning_several_chunks_to_the_new_content() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.append_left(4, "x")?;
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+
+    assert_eq!(s.to_string(), "abcDEFghijkl");
+    assert_eq!(s.generated_index_for(3), Some(3));
+    assert_eq!(s.generated_index_for(4), Some(3));
+    assert_eq!(s.generated_index_for(5), Some(3));
+    assert_eq!(s.generated_index_for(6), Some(6));
+
+    s.remove(0, 3)?;
+    s._move(5, 6, 0)?;
+
+    assert_eq!(s.to_string(), "DEFghijkl");
+    assert_eq!(s.generated_index_for(5), Some(0));
+
+    s.remove(3, 4)?;
+
+    assert_eq!(s.to_string(), "ghijkl");
+    assert_eq!(s.generated_index_for(4), None);
+    assert_eq!(s.generated_index_for(5), None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_be_the_reverse_of_original_index_for() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.append_left(2, "xx")?;
+    s._move(0, 4, 8)?;
+    s.remove(9, 10)?;
+
+    for i in 0..12u32 {
+      if let Some(generated_index) = s.generated_index_for(i) {
+        assert_eq!(s.original_index_for(generated_index), Some(i));
+      }
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_count_utf16_code_units() -> Result {
+    let mut s = MagicString::new_with_options(
+      "😀a中b",
+      MagicStringOptions {
+        index_unit: IndexUnit::Utf16,
+      },
+    );
+    s.prepend("中")?;
+
+    assert_eq!(s.generated_index_for(0), Some(1));
+    assert_eq!(s.generated_index_for(1), None);
+    assert_eq!(s.generated_index_for(2), Some(3));
+    assert_eq!(s.generated_index_for(4), Some(5));
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_none_for_indices_inside_a_character() -> Result {
+    let s = MagicString::new("a中b");
+
+    assert_eq!(s.generated_index_for(2), None);
+    assert_eq!(s.generated_index_for(4), Some(4));
+
+    Ok(())
+  }
+}
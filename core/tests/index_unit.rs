@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod index_unit {
+  use magic_string::{
+    GenerateDecodedMapOptions, IndentOptions, IndexUnit, MagicString, MagicStringErrorType,
+    MagicStringOptions, OverwriteOptions, Result,
+  };
+
+  fn utf16(str: &str) -> MagicString {
+    MagicString::new_with_options(
+      str,
+      MagicStringOptions {
+        index_unit: IndexUnit::Utf16,
+      },
+    )
+  }
+
+  #[test]
+  fn should_default_to_utf8_indices() -> Result {
+    let mut s = MagicString::new("中文");
+    s.overwrite(3, 6, "字", OverwriteOptions::default())?;
+
+    assert_eq!(s.to_string(), "中字");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_overwrite_with_utf16_indices() -> Result {
+    let mut s = utf16("const s = '中文😀'; foo();");
+
+    s.overwrite(18, 21, "bar", OverwriteOptions::default())?;
+    s.overwrite(12, 15, "字", OverwriteOptions::default())?;
+
+    assert_eq!(s.to_string(), "const s = '中字'; bar();");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_insert_and_remove_with_utf16_indices() -> Result {
+    let mut s = utf16("😀a中b");
+
+    s.append_left(2, "(")?;
+    s.prepend_right(4, ")")?;
+    s.remove(3, 4)?;
+
+    assert_eq!(s.to_string(), "😀(a)b");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_count_negative_indices_from_the_end_in_utf16() -> Result {
+    let mut s = utf16("😀ab中");
+
+    s.overwrite(-2, -1, "B", OverwriteOptions::default())?;
+    s.remove(-1, 5)?;
+
+    assert_eq!(s.to_string(), "😀aB");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_slice_snip_and_move_with_utf16_indices() -> Result {
+    let mut s = utf16("😀abc中def");
+
+    assert_eq!(s.slice(2, 6)?, "abc中");
+    assert_eq!(s.snip(5, 9)?.to_string(), "中def");
+
+    s._move(2, 5, 9)?;
+    assert_eq!(s.to_string(), "😀中defabc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_replace_with_utf16_indices() -> Result {
+    let mut s = utf16("😀 foo 中 foo");
+
+    s.replace_all("foo", "bar")?;
+    s.append_left(6, "!")?;
+
+    assert_eq!(s.to_string(), "😀 bar! 中 bar");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_exclude_utf16_ranges_from_indentation() -> Result {
+    let mut s = utf16("😀\n中\nabc");
+
+    s.indent(
+      Some("  "),
+      IndentOptions {
+        exclude: Some(vec![vec![3, 5]]),
+        ..IndentOptions::default()
+      },
+    )?;
+
+    assert_eq!(s.to_string(), "  😀\n中\n  abc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_generate_the_same_sourcemap_as_utf8_indices() -> Result {
+    let mut s = utf16("let s = \"😀\"; foo();");
+    s.overwrite(14, 17, "bar", OverwriteOptions::default())?;
+
+    let mut t = MagicString::new("let s = \"😀\"; foo();");
+    t.overwrite(16, 19, "bar", OverwriteOptions::default())?;
+
+    assert_eq!(s.to_string(), t.to_string());
+    assert_eq!(
+      s.generate_map(GenerateDecodedMapOptions::default())?
+        .mappings,
+      t.generate_map(GenerateDecodedMapOptions::default())?
+        .mappings,
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_error_on_indices_inside_a_surrogate_pair() -> Result {
+    let mut s = utf16("a😀b");
+
+    assert!(s.overwrite(2, 3, "x", OverwriteOptions::default()).is_err());
+    assert_eq!(
+      s.append_left(2, "x").unwrap_err().error_type,
+      MagicStringErrorType::MagicStringCharBoundaryError
+    );
+    assert!(s.append_left(5, "x").is_err());
+    assert_eq!(s.to_string(), "a😀b");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_count_the_length_in_utf16() -> Result {
+    let mut s = utf16("中文😀abc");
+    assert_eq!(s.len_in_index_unit(), 7);
+
+    s.remove(2, 4)?;
+    s.prepend("é")?;
+    s.overwrite(4, 5, "😀😀", OverwriteOptions::default())?;
+
+    assert_eq!(s.to_string(), "é中文😀😀bc");
+    assert_eq!(s.len_in_index_unit(), 9);
+    assert_eq!(s.len(), s.to_string().len());
+
+    Ok(())
+  }
+}
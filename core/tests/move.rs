@@ -161,4 +161,21 @@ mod _move {
     assert_eq!(s_ptr, result_ptr);
     Ok(())
   }
+  #[test]
+  fn should_move_multiple_chunks_to_the_end() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+    s.append_left(1, "X")?._move(0, 3, 12)?;
+
+    assert_eq!(s.to_string(), "defghijklaXbc");
+
+    // `a` is not at the start anymore
+    s._move(3, 6, 0)?;
+    assert_eq!(s.to_string(), "ghijkldefaXbc");
+
+    s.append(" ")?.trim_end(None)?;
+    s._move(9, 12, 0)?;
+    assert_eq!(s.to_string(), "ghidefjklaXbc");
+
+    Ok(())
+  }
 }
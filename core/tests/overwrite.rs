@@ -122,7 +122,15 @@ mod overwrite {
     s.prepend_right(1, "^")?;
     s.append_left(3, "!")?;
     s.prepend_right(3, "?")?;
-    s.overwrite(1, 3, "...", OverwriteOptions { content_only: true })?;
+    s.overwrite(
+      1,
+      3,
+      "...",
+      OverwriteOptions {
+        content_only: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
 
     assert_eq!(s.to_string(), "a&^...!?defghijkl");
 
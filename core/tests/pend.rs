@@ -186,4 +186,19 @@ mod append_right {
 
     Ok(())
   }
+
+  #[test]
+  fn should_insert_at_scattered_indices_in_any_order() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    for index in [6, 2, 10, 0, 12, 4, 8, 1, 11] {
+      s.append_right(index, index.to_string().as_str())?;
+    }
+    s.remove(3, 5)?;
+
+    assert_eq!(s.to_string(), "0a1b2cf6gh8ij10k11l12");
+    assert_eq!(s.original_index_for(9), Some(7));
+
+    Ok(())
+  }
 }
@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod remap {
+  use magic_string::{
+    Bundle, BundleOptions, GenerateDecodedMapOptions, Hires, MagicString, OverwriteOptions, Result,
+    SourceMap,
+  };
+
+  fn hires_map_of(s: &MagicString, source: &str) -> Result<SourceMap> {
+    s.generate_map(GenerateDecodedMapOptions {
+      source: Some(source.to_owned()),
+      include_content: true,
+      hires: Hires::Full,
+      ..GenerateDecodedMapOptions::default()
+    })
+  }
+
+  #[test]
+  fn should_trace_mappings_through_the_input_map() -> Result {
+    let mut ts = MagicString::new("let foo: number = 1;");
+    ts.remove(7, 15)?;
+    let input_map = hires_map_of(&ts, "index.ts")?;
+
+    let mut js = MagicString::new(ts.to_string().as_str());
+    js.overwrite(
+      4,
+      7,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    assert_eq!(js.to_string(), "let bar = 1;");
+
+    let map = js.generate_decoded_map_with_input(
+      GenerateDecodedMapOptions {
+        file: Some("index.js".to_owned()),
+        include_content: true,
+        ..GenerateDecodedMapOptions::default()
+      },
+      &input_map,
+    )?;
+
+    assert_eq!(map.file, Some("index.js".to_owned()));
+    assert_eq!(map.sources, vec![Some("index.ts".to_owned())]);
+    assert_eq!(
+      map.sources_content,
+      vec![Some("let foo: number = 1;".to_owned())]
+    );
+    assert_eq!(map.names, vec!["foo".to_owned()]);
+    assert_eq!(
+      map.mappings,
+      vec![vec![
+        vec![0, 0, 0, 0],
+        vec![4, 0, 0, 4, 0],
+        vec![3, 0, 0, 11]
+      ]]
+    );
+
+    // the same as making both edits on the original string
+    let mut s = MagicString::new("let foo: number = 1;");
+    s.remove(7, 15)?;
+    s.overwrite(
+      4,
+      7,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    assert_eq!(
+      js.generate_map_with_input(GenerateDecodedMapOptions::default(), &input_map)?
+        .mappings,
+      s.generate_map(GenerateDecodedMapOptions::default())?
+        .mappings
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_prefer_names_of_the_input_map() -> Result {
+    let mut s = MagicString::new("foo();");
+    s.overwrite(
+      0,
+      3,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    let input_map = hires_map_of(&s, "input.js")?;
+
+    let mut output = MagicString::new(s.to_string().as_str());
+    output.overwrite(
+      0,
+      3,
+      "baz",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+
+    let map =
+      output.generate_decoded_map_with_input(GenerateDecodedMapOptions::default(), &input_map)?;
+
+    assert_eq!(map.names, vec!["foo".to_owned()]);
+    assert_eq!(
+      map.mappings,
+      vec![vec![vec![0, 0, 0, 0, 0], vec![3, 0, 0, 3]]]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_drop_segments_which_can_not_be_traced() -> Result {
+    let mut s = MagicString::new("abc");
+    s.prepend("// header\n")?;
+    let input_map = hires_map_of(&s, "input.js")?;
+
+    let output = MagicString::new(s.to_string().as_str());
+    let map =
+      output.generate_decoded_map_with_input(GenerateDecodedMapOptions::default(), &input_map)?;
+
+    assert_eq!(map.mappings, vec![vec![], vec![vec![0, 0, 0, 0]]]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_only_keep_referenced_sources() -> Result {
+    let mut bundle = Bundle::new(BundleOptions::default());
+    bundle.add_source(Some("a.js"), MagicString::new("const a = 1"))?;
+    bundle.add_source(Some("b.js"), MagicString::new("const b = 2"))?;
+    bundle.add_source(Some("c.js"), MagicString::new("const c = 3"))?;
+    let input_map = bundle.generate_map(GenerateDecodedMapOptions {
+      include_content: true,
+      hires: Hires::Full,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    let mut s = MagicString::new(bundle.to_string().as_str());
+    s.remove(0, 12)?;
+    s.overwrite(24, 29, "let", OverwriteOptions::default())?;
+    assert_eq!(s.to_string(), "const b = 2\nlet c = 3");
+
+    let map = s.generate_decoded_map_with_input(
+      GenerateDecodedMapOptions {
+        include_content: true,
+        ..GenerateDecodedMapOptions::default()
+      },
+      &input_map,
+    )?;
+
+    assert_eq!(
+      map.sources,
+      vec![Some("b.js".to_owned()), Some("c.js".to_owned())]
+    );
+    assert_eq!(
+      map.sources_content,
+      vec![
+        Some("const b = 2".to_owned()),
+        Some("const c = 3".to_owned())
+      ]
+    );
+    assert_eq!(
+      map.mappings,
+      vec![
+        vec![vec![0, 0, 0, 0]],
+        vec![vec![0, 1, 0, 0], vec![3, 0, 0, 5]],
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_fall_back_to_the_source_root_of_the_input_map() -> Result {
+    let s = MagicString::new("abc");
+    let input_map = s.generate_map(GenerateDecodedMapOptions {
+      source_root: Some("src/".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    let map =
+      s.generate_decoded_map_with_input(GenerateDecodedMapOptions::default(), &input_map)?;
+    assert_eq!(map.source_root, Some("src/".to_owned()));
+
+    let map = s.generate_decoded_map_with_input(
+      GenerateDecodedMapOptions {
+        source_root: Some("./".to_owned()),
+        ..GenerateDecodedMapOptions::default()
+      },
+      &input_map,
+    )?;
+    assert_eq!(map.source_root, Some("./".to_owned()));
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_keep_ignored_sources_of_the_input_map() -> Result {
+    let mut bundle = Bundle::new(BundleOptions::default());
+    bundle.add_source(Some("vendor.js"), MagicString::new("const a = 1"))?;
+    bundle.add_source(Some("index.js"), MagicString::new("const b = 2"))?;
+    let input_map = bundle.generate_map(GenerateDecodedMapOptions {
+      ignore_list: vec![0],
+      ..GenerateDecodedMapOptions::default()
+    })?;
+    assert_eq!(input_map.ignore_list, vec![0]);
+
+    // `index.js` comes first in the output, so `vendor.js` is listed as the second source
+    let mut s = MagicString::new(bundle.to_string().as_str());
+    s._move(0, 12, 23)?;
+    s.append("\n")?;
+
+    let map = s.generate_decoded_map_with_input(
+      GenerateDecodedMapOptions {
+        debug_id: Some("output".to_owned()),
+        ..GenerateDecodedMapOptions::default()
+      },
+      &input_map,
+    )?;
+
+    assert_eq!(
+      map.sources,
+      vec![Some("index.js".to_owned()), Some("vendor.js".to_owned())]
+    );
+    assert_eq!(map.ignore_list, vec![1]);
+    assert_eq!(map.debug_id, Some("output".to_owned()));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod replace {
+  use magic_string::{
+    Captures, GenerateDecodedMapOptions, MagicString, OverwriteOptions, Regex, Result,
+  };
+
+  #[test]
+  fn should_replace_the_first_match_of_a_string() -> Result {
+    let mut s = MagicString::new("1 2 1 2");
+
+    s.replace("2", "3")?;
+    assert_eq!(s.to_string(), "1 3 1 2");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_treat_a_string_as_regexp() -> Result {
+    let mut s = MagicString::new("1234");
+
+    s.replace(".", "*")?;
+    assert_eq!(s.to_string(), "1234");
+
+    s.replace_all(".", "*")?;
+    assert_eq!(s.to_string(), "1234");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_replace_all_matches_of_a_string() -> Result {
+    let mut s = MagicString::new("1 2 1 2");
+
+    s.replace_all("2", "3")?;
+    assert_eq!(s.to_string(), "1 3 1 3");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_replace_with_regexp_and_substitutions() -> Result {
+    let mut s = MagicString::new("1 2 3 4 a b c");
+
+    s.replace(Regex::new(r"(\d)").unwrap(), "xx$1${1}0")?;
+    assert_eq!(s.to_string(), "xx110 2 3 4 a b c");
+
+    let mut s = MagicString::new("1 2 3 4 a b c");
+
+    s.replace_all(Regex::new(r"(\d)").unwrap(), "xx$1${1}0")?;
+    assert_eq!(s.to_string(), "xx110 xx220 xx330 xx440 a b c");
+
+    s.overwrite(2, 3, "oo", OverwriteOptions::default())?;
+    assert_eq!(s.to_string(), "xx110 oo xx330 xx440 a b c");
+
+    let digit = Regex::new(r"\d").unwrap();
+    let mut s = MagicString::new("1 2 3 4 a b c");
+
+    s.replace_all(&digit, "$$")?;
+    assert_eq!(s.to_string(), "$ $ $ $ a b c");
+
+    let mut s = MagicString::new("1 2 3 4 a b c");
+
+    s.replace(digit, "$$")?;
+    assert_eq!(s.to_string(), "$ 2 3 4 a b c");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_replace_with_named_capture_groups() -> Result {
+    let mut s = MagicString::new("import foo from 'foo'");
+
+    s.replace(
+      Regex::new(r"import (?P<name>\w+) from").unwrap(),
+      "const ${name} = require(",
+    )?;
+    assert_eq!(s.to_string(), "const foo = require( 'foo'");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_replace_with_a_closure() -> Result {
+    let mut s = MagicString::new("hey this is magic");
+
+    s.replace_all(Regex::new(r"(\w)(\w+)").unwrap(), |caps: &Captures| {
+      format!("{}{}", caps[1].to_uppercase(), &caps[2])
+    })?;
+    assert_eq!(s.to_string(), "Hey This Is Magic");
+
+    let mut s = MagicString::new("hey this is magic");
+    let mut offsets = vec![];
+
+    s.replace_all(Regex::new(r"\w+").unwrap(), |caps: &Captures| {
+      let matched = caps.get(0).unwrap();
+      offsets.push(matched.start());
+      matched.as_str().len().to_string()
+    })?;
+    assert_eq!(s.to_string(), "3 4 2 5");
+    assert_eq!(offsets, vec![0, 4, 9, 12]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_when_replacing_edited_content() -> Result {
+    let mut s = MagicString::new("1 2 3 4 a b c");
+
+    s.overwrite(0, 3, "x", OverwriteOptions::default())?;
+    assert!(s.replace_all(Regex::new(r"\d").unwrap(), "y").is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_replace_any_match_if_one_of_them_fails() -> Result {
+    let mut s = MagicString::new("foo foo foo");
+
+    s.overwrite(3, 5, "__", OverwriteOptions::default())?;
+    assert!(s.replace_all("foo", "bar").is_err());
+    assert_eq!(s.to_string(), "foo__oo foo");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_map_replaced_content_to_the_original_positions() -> Result {
+    let mut s = MagicString::new("if (process.env.NODE_ENV) {\n  process.env.NODE_ENV\n}");
+
+    s.replace_all("process.env.NODE_ENV", "'production'")?;
+    assert_eq!(s.to_string(), "if ('production') {\n  'production'\n}");
+
+    let map = s.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(
+      map.mappings,
+      vec![
+        vec![vec![0, 0, 0, 0], vec![4, 0, 0, 4], vec![12, 0, 0, 20]],
+        vec![vec![0, 0, 1, -24], vec![2, 0, 0, 2], vec![12, 0, 0, 20]],
+        vec![vec![0, 0, 1, -22]],
+      ]
+    );
+
+    Ok(())
+  }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod reset {
+  use magic_string::{GenerateDecodedMapOptions, Hires, MagicString, OverwriteOptions, Result};
+
+  #[test]
+  fn should_reset_removed_characters() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.remove(1, 5)?;
+    assert_eq!(s.to_string(), "afghijkl");
+
+    s.reset(1, 5)?;
+    assert_eq!(s.to_string(), "abcdefghijkl");
+
+    s.remove(1, 5)?;
+    assert_eq!(s.to_string(), "afghijkl");
+
+    s.reset(2, 3)?;
+    assert_eq!(s.to_string(), "acfghijkl");
+
+    s.reset(1, 2)?;
+    assert_eq!(s.to_string(), "abcfghijkl");
+
+    s.reset(3, 4)?;
+    assert_eq!(s.to_string(), "abcdfghijkl");
+
+    s.reset(4, 5)?;
+    assert_eq!(s.to_string(), "abcdefghijkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_reset_overwritten_characters() -> Result {
+    let mut s = MagicString::new("abcdefghi");
+
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+    assert_eq!(s.to_string(), "abcDEFghi");
+
+    s.reset(3, 6)?;
+    assert_eq!(s.to_string(), "abcdefghi");
+
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+    s.remove(2, 7)?;
+    assert_eq!(s.to_string(), "abhi");
+
+    s.reset(2, 7)?;
+    assert_eq!(s.to_string(), "abcdefghi");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_remove_inserted_content_inside_the_range() -> Result {
+    let mut s = MagicString::new("abcdefghi");
+
+    s.append_left(3, "(")?;
+    s.prepend_right(6, ")")?;
+    s.append_left(9, "!")?;
+    assert_eq!(s.to_string(), "abc(def)ghi!");
+
+    s.reset(0, 6)?;
+    assert_eq!(s.to_string(), "abcdef)ghi!");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_reset_moved_characters_in_place() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s._move(3, 6, 9)?;
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+    s.remove(6, 9)?;
+    assert_eq!(s.to_string(), "abcDEFjkl");
+
+    // same as `overwrite`, ranges across moved chunks cannot be edited at once
+    assert!(s.reset(3, 9).is_err());
+
+    s.reset(3, 6)?;
+    s.reset(6, 9)?;
+    assert_eq!(s.to_string(), "abcghidefjkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_if_resetting_a_part_of_an_edited_range() -> Result {
+    let mut s = MagicString::new("abcdefghi");
+
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+
+    assert!(s.reset(4, 6).is_err());
+    assert!(s.reset(6, 3).is_err());
+
+    s.reset(3, 3)?;
+    assert_eq!(s.to_string(), "abcDEFghi");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_restore_sourcemap_mappings() -> Result {
+    let mut s = MagicString::new("abcdefghi");
+    let map = s.generate_map(GenerateDecodedMapOptions {
+      hires: Hires::Full,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    s.overwrite(
+      3,
+      6,
+      "DEF",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.reset(3, 6)?;
+
+    assert_eq!(
+      s.generate_map(GenerateDecodedMapOptions {
+        hires: Hires::Full,
+        ..GenerateDecodedMapOptions::default()
+      })?
+      .mappings,
+      map.mappings
+    );
+
+    Ok(())
+  }
+}
@@ -0,0 +1,303 @@
+#[cfg(test)]
+mod sectioned_source_map {
+  use magic_string::{
+    Bundle, BundleOptions, GenerateDecodedMapOptions, Hires, MagicString, OverwriteOptions, Result,
+    Section, SectionOffset, SectionedSourceMap, SourceMap,
+  };
+
+  #[test]
+  fn should_generate_a_section_for_each_source() -> Result {
+    let mut bundle = Bundle::new(BundleOptions {
+      intro: Some("/* intro */ ".to_owned()),
+      ..BundleOptions::default()
+    });
+
+    let mut a = MagicString::new("const foo = 1;\nexport { foo };");
+    a.overwrite(
+      6,
+      9,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    bundle.add_source(Some("src/a.js"), a)?;
+    bundle.append("/* separator */", Some("\n"))?;
+
+    let mut b = MagicString::new("foo();\nbaz();");
+    b.overwrite(
+      0,
+      3,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    b.prepend("// b.js\n")?;
+    bundle.add_source(Some("src/b.js"), b)?;
+    bundle.append("/* separator */", Some("\n"))?;
+    bundle.add_source(
+      Some("src/a.js"),
+      MagicString::new("const foo = 1;\nexport { foo };"),
+    )?;
+
+    let map = bundle.generate_sectioned_map(GenerateDecodedMapOptions {
+      file: Some("dist/bundle.js".to_owned()),
+      include_content: true,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    assert_eq!(map.version, 3);
+    assert_eq!(map.file, Some("bundle.js".to_owned()));
+    assert_eq!(
+      map
+        .sections
+        .iter()
+        .map(|section| section.offset)
+        .collect::<Vec<_>>(),
+      vec![
+        SectionOffset {
+          line: 0,
+          column: 12
+        },
+        SectionOffset { line: 3, column: 0 },
+        SectionOffset { line: 7, column: 0 },
+      ]
+    );
+    assert_eq!(
+      map.sections[1].map.sources,
+      vec![Some("../src/b.js".to_owned())]
+    );
+    assert_eq!(
+      map.sections[1].map.sources_content,
+      vec![Some("foo();\nbaz();".to_owned())]
+    );
+    assert_eq!(map.sections[1].map.names, vec!["foo".to_owned()]);
+    assert_eq!(map.sections[1].map.file, None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_serialize_sections() -> Result {
+    let map = SectionedSourceMap::new(
+      Some("bundle.js"),
+      vec![Section {
+        offset: SectionOffset { line: 1, column: 2 },
+        map: SourceMap::new("AAAA", None, vec![], vec![], None, vec![Some("a.js")]),
+      }],
+    );
+
+    assert_eq!(
+      map.to_string()?,
+      r#"{"version":3,"file":"bundle.js","sections":[{"offset":{"line":1,"column":2},"map":{"version":3,"mappings":"AAAA","names":[],"sources":["a.js"],"sourcesContent":[],"file":null}}]}"#
+    );
+    assert!(map
+      .to_url()?
+      .starts_with("data:application/json;charset=utf-8;base64,"));
+
+    let parsed = SectionedSourceMap::from_json(map.to_string()?.as_str())?;
+    assert_eq!(parsed.to_string()?, map.to_string()?);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_flatten_to_the_same_map_as_generate_map() -> Result {
+    let mut bundle = Bundle::new(BundleOptions {
+      intro: Some("/* intro */ ".to_owned()),
+      ..BundleOptions::default()
+    });
+
+    let mut a = MagicString::new("const foo = 1;\nexport { foo };");
+    a.overwrite(
+      6,
+      9,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    bundle.add_source(Some("src/a.js"), a)?;
+    bundle.append("/* separator */", Some("\n"))?;
+
+    let mut b = MagicString::new("foo();\nbaz();");
+    b.overwrite(
+      0,
+      3,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    b.prepend("// b.js\n")?;
+    bundle.add_source(Some("src/b.js"), b)?;
+    bundle.append("/* separator */", Some("\n"))?;
+    bundle.add_source(
+      Some("src/a.js"),
+      MagicString::new("const foo = 1;\nexport { foo };"),
+    )?;
+
+    for hires in [Hires::Off, Hires::Boundary, Hires::Full] {
+      let options = GenerateDecodedMapOptions {
+        file: Some("dist/bundle.js".to_owned()),
+        include_content: true,
+        hires,
+        ..GenerateDecodedMapOptions::default()
+      };
+
+      let flattened = bundle.generate_sectioned_map(options.clone())?.flatten()?;
+      let map = bundle.generate_map(options)?;
+
+      assert_eq!(flattened.file, map.file);
+      assert_eq!(flattened.sources, map.sources);
+      assert_eq!(flattened.sources_content, map.sources_content);
+      assert_eq!(flattened.names, map.names);
+      assert_eq!(flattened.mappings, map.mappings);
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_prepend_source_root_when_flattening() -> Result {
+    let map = SectionedSourceMap::new(
+      None,
+      vec![
+        Section {
+          offset: SectionOffset::default(),
+          map: SourceMap::new(
+            "AAAA",
+            None,
+            vec![],
+            vec![],
+            Some("src"),
+            vec![Some("a.js")],
+          ),
+        },
+        Section {
+          offset: SectionOffset { line: 0, column: 4 },
+          map: SourceMap::new(
+            "AAAA",
+            None,
+            vec![],
+            vec![],
+            Some("src/"),
+            vec![Some("a.js")],
+          ),
+        },
+        Section {
+          offset: SectionOffset { line: 0, column: 8 },
+          map: SourceMap::new("AAAA", None, vec![], vec![], None, vec![Some("a.js")]),
+        },
+      ],
+    )
+    .flatten()?;
+
+    assert_eq!(
+      map.sources,
+      vec![Some("src/a.js".to_owned()), Some("a.js".to_owned())]
+    );
+    assert_eq!(map.source_root, None);
+    assert!(map.sources_content.is_empty());
+    assert_eq!(map.mappings, "AAAA,IAAA,ICAA");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_keep_unmapped_segments_when_flattening() -> Result {
+    let map = SectionedSourceMap::new(
+      None,
+      vec![Section {
+        offset: SectionOffset { line: 1, column: 2 },
+        map: SourceMap::new(
+          "AAAA,C;A,CAAC",
+          None,
+          vec![],
+          vec![],
+          None,
+          vec![Some("a.js")],
+        ),
+      }],
+    )
+    .flatten()?;
+
+    assert_eq!(map.mappings, ";EAAA,C;A,CAAC");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_map_the_ignore_list_to_sections() -> Result {
+    let mut bundle = Bundle::new(BundleOptions {
+      intro: Some("/* intro */ ".to_owned()),
+      ..BundleOptions::default()
+    });
+
+    let mut a = MagicString::new("const foo = 1;\nexport { foo };");
+    a.overwrite(
+      6,
+      9,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    bundle.add_source(Some("src/a.js"), a)?;
+    bundle.append("/* separator */", Some("\n"))?;
+
+    let mut b = MagicString::new("foo();\nbaz();");
+    b.overwrite(
+      0,
+      3,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    b.prepend("// b.js\n")?;
+    bundle.add_source(Some("src/b.js"), b)?;
+    bundle.append("/* separator */", Some("\n"))?;
+    bundle.add_source(
+      Some("src/a.js"),
+      MagicString::new("const foo = 1;\nexport { foo };"),
+    )?;
+
+    let options = GenerateDecodedMapOptions {
+      ignore_list: vec![1],
+      debug_id: Some("bundle".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    };
+
+    let map = bundle.generate_sectioned_map(options.clone())?;
+    assert_eq!(
+      map
+        .sections
+        .iter()
+        .map(|section| section.map.ignore_list.to_owned())
+        .collect::<Vec<_>>(),
+      vec![vec![], vec![0], vec![]]
+    );
+    assert!(map
+      .sections
+      .iter()
+      .all(|section| section.map.debug_id.is_none()));
+
+    let flattened = map.flatten()?;
+    assert_eq!(
+      flattened.ignore_list,
+      bundle.generate_map(options)?.ignore_list
+    );
+    assert_eq!(flattened.ignore_list, vec![1]);
+    assert_eq!(flattened.debug_id, None);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod slice {
+  use magic_string::{MagicString, MagicStringErrorType, OverwriteOptions, Result};
+
+  #[test]
+  fn should_return_the_generated_content_between_the_specified_original_characters() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    assert_eq!(s.slice(3, 9)?, "defghi");
+    s.overwrite(4, 8, "XX", OverwriteOptions::default())?;
+    assert_eq!(s.slice(3, 9)?, "dXXi");
+    s.overwrite(2, 10, "ZZ", OverwriteOptions::default())?;
+    assert_eq!(s.slice(1, 11)?, "bZZk");
+    assert_eq!(s.slice(2, 10)?, "ZZ");
+
+    assert!(s.slice(3, 9).is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_allow_negative_numbers_as_arguments() -> Result {
+    let s = MagicString::new("abcdefghijkl");
+
+    assert_eq!(s.slice(-3, 12)?, "jkl");
+    assert_eq!(s.slice(0, -3)?, "abcdefghi");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_include_inserted_characters_respecting_insertion_direction() -> Result {
+    let mut s = MagicString::new("abefij");
+
+    s.prepend_right(2, "cd")?;
+    s.append_left(4, "gh")?;
+
+    assert_eq!(s.slice(0, 6)?, "abcdefghij");
+    assert_eq!(s.slice(1, 5)?, "bcdefghi");
+    assert_eq!(s.slice(2, 4)?, "cdefgh");
+    assert_eq!(s.slice(3, 4)?, "fgh");
+    assert_eq!(s.slice(0, 2)?, "ab");
+    assert_eq!(s.slice(0, 3)?, "abcde");
+    assert_eq!(s.slice(4, 6)?, "ij");
+    assert_eq!(s.slice(3, 6)?, "fghij");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_support_characters_moved_outward() -> Result {
+    let mut s = MagicString::new("abcdEFghIJklmn");
+
+    s._move(4, 6, 2)?;
+    s._move(8, 10, 12)?;
+    assert_eq!(s.to_string(), "abEFcdghklIJmn");
+
+    assert_eq!(s.slice(1, -1)?, "bEFcdghklIJm");
+    assert_eq!(s.slice(2, -2)?, "cdghkl");
+    assert_eq!(s.slice(3, -3)?, "dghk");
+    assert_eq!(s.slice(4, -4)?, "EFcdghklIJ");
+    assert_eq!(s.slice(5, -5)?, "FcdghklI");
+    assert_eq!(s.slice(6, -6)?, "gh");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_support_characters_moved_inward() -> Result {
+    let mut s = MagicString::new("abCDefghijKLmn");
+
+    s._move(2, 4, 6)?;
+    s._move(10, 12, 8)?;
+    assert_eq!(s.to_string(), "abefCDghKLijmn");
+
+    assert_eq!(s.slice(1, -1)?, "befCDghKLijm");
+    assert_eq!(s.slice(2, -2)?, "CDghKL");
+    assert_eq!(s.slice(3, -3)?, "DghK");
+    assert_eq!(s.slice(4, -4)?, "efCDghKLij");
+    assert_eq!(s.slice(5, -5)?, "fCDghKLi");
+    assert_eq!(s.slice(6, -6)?, "gh");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_support_characters_moved_opposing() -> Result {
+    let mut s = MagicString::new("abCDefghIJkl");
+
+    s._move(2, 4, 8)?;
+    s._move(8, 10, 4)?;
+    assert_eq!(s.to_string(), "abIJefghCDkl");
+
+    assert_eq!(s.slice(1, -1)?, "bIJefghCDk");
+    assert_eq!(s.slice(2, -2)?, "");
+    assert_eq!(s.slice(3, -3)?, "");
+    assert_eq!(s.slice(-3, 3)?, "JefghC");
+    assert_eq!(s.slice(4, -4)?, "efgh");
+    assert_eq!(s.slice(0, 3)?, "abIJefghC");
+    assert_eq!(s.slice(3, 12)?, "Dkl");
+    assert_eq!(s.slice(0, -3)?, "abI");
+    assert_eq!(s.slice(-3, 12)?, "JefghCDkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_error_if_replaced_characters_are_used_as_slice_anchors() -> Result {
+    let mut s = MagicString::new("abcdef");
+    s.overwrite(2, 4, "CD", OverwriteOptions::default())?;
+
+    let err = s.slice(2, 3).unwrap_err();
+    assert_eq!(
+      err.error_type,
+      MagicStringErrorType::MagicStringReplacedAnchorError
+    );
+    assert!(err.reason.unwrap().contains("slice end anchor"));
+
+    let err = s.slice(3, 4).unwrap_err();
+    assert!(err.reason.unwrap().contains("slice start anchor"));
+
+    let err = s.slice(3, 5).unwrap_err();
+    assert!(err.reason.unwrap().contains("slice start anchor"));
+
+    assert_eq!(s.slice(1, 5)?, "bCDe");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_error_if_removed_characters_are_used_as_slice_anchors() -> Result {
+    let mut s = MagicString::new("abcdef");
+    s.remove(2, 4)?;
+
+    assert!(s.slice(3, 5).is_err());
+    assert_eq!(s.slice(1, 5)?, "be");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_not_error_if_slice_is_after_removed_characters() -> Result {
+    let mut s = MagicString::new("abcdef");
+
+    s.remove(0, 2)?;
+
+    assert_eq!(s.slice(2, 4)?, "cd");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_if_out_of_range() {
+    let s = MagicString::new("abcdef");
+
+    assert!(s.slice(0, 7).is_err());
+    assert!(s.slice(-7, 3).is_err());
+  }
+
+  #[test]
+  fn should_return_an_error_if_start_is_after_end() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    let err = s.slice(5, 3).unwrap_err();
+    assert_eq!(
+      err.error_type,
+      MagicStringErrorType::MagicStringOutOfRangeError
+    );
+    assert!(s.slice(-1, 3).is_err());
+    assert!(s.slice(5, 0).is_err());
+
+    s.remove(6, 9)?;
+    assert!(s.slice(10, 2).is_err());
+    assert_eq!(s.slice(3, 3)?, "");
+
+    Ok(())
+  }
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod snip {
+  use magic_string::{GenerateDecodedMapOptions, MagicString, OverwriteOptions, Result};
+
+  #[test]
+  fn should_return_a_clone_with_content_outside_start_and_end_removed() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.overwrite(6, 9, "GHI", OverwriteOptions::default())?;
+
+    let snippet = s.snip(3, 9)?;
+    assert_eq!(snippet.to_string(), "defGHI");
+    assert_eq!(s.to_string(), "abcdefGHIjkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_snip_from_the_start() -> Result {
+    let s = MagicString::new("abcdefghijkl");
+    let snippet = s.snip(0, 6)?;
+
+    assert_eq!(snippet.to_string(), "abcdef");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_snip_from_the_end() -> Result {
+    let s = MagicString::new("abcdefghijkl");
+    let snippet = s.snip(6, 12)?;
+
+    assert_eq!(snippet.to_string(), "ghijkl");
+
+    let snippet = s.snip(-6, -3)?;
+
+    assert_eq!(snippet.to_string(), "ghi");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_respect_original_indices() -> Result {
+    let s = MagicString::new("abcdefghijkl");
+    let mut snippet = s.snip(3, 9)?;
+
+    snippet.overwrite(6, 9, "GHI", OverwriteOptions::default())?;
+    assert_eq!(snippet.to_string(), "defGHI");
+    assert_eq!(s.to_string(), "abcdefghijkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_map_to_the_original_source() -> Result {
+    let mut s =
+      MagicString::new("function foo () {\n  return 1\n}\nfunction bar () {\n  return 2\n}");
+
+    s.overwrite(51, 57, "answer", OverwriteOptions::default())?;
+
+    let snippet = s.snip(31, 59)?;
+    assert_eq!(snippet.to_string(), "function bar () {\n  answer 2");
+
+    let map = snippet.generate_decoded_map(GenerateDecodedMapOptions::default())?;
+    assert_eq!(
+      map.mappings,
+      vec![
+        vec![vec![0, 0, 3, 0]],
+        vec![vec![0, 0, 1, 0], vec![2, 0, 0, 2], vec![6, 0, 0, 6]]
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_if_start_is_greater_than_end() {
+    let s = MagicString::new("abcdefghijkl");
+
+    assert!(s.snip(6, 3).is_err());
+  }
+}
@@ -0,0 +1,273 @@
+#[cfg(test)]
+mod source_map {
+  use magic_string::{
+    decode_mappings, DecodedMap, GenerateDecodedMapOptions, MagicString, MagicStringErrorType,
+    OverwriteOptions, Result, SourceMap,
+  };
+
+  #[test]
+  fn should_round_trip_json() -> Result {
+    let mut s = MagicString::new("function foo() {}\nfoo();");
+    s.overwrite(
+      9,
+      12,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("// header\n")?;
+
+    let map = s.generate_map(GenerateDecodedMapOptions {
+      file: Some("output.js".to_owned()),
+      source_root: Some("./".to_owned()),
+      source: Some("input.js".to_owned()),
+      include_content: true,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    let parsed = SourceMap::from_json(map.to_string()?.as_str())?;
+
+    assert_eq!(parsed.version, 3);
+    assert_eq!(parsed.file, map.file);
+    assert_eq!(parsed.sources, map.sources);
+    assert_eq!(parsed.source_root, map.source_root);
+    assert_eq!(parsed.sources_content, map.sources_content);
+    assert_eq!(parsed.names, map.names);
+    assert_eq!(parsed.mappings, map.mappings);
+    assert_eq!(parsed.to_string()?, map.to_string()?);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_round_trip_url() -> Result {
+    let mut s = MagicString::new("function foo() {}\nfoo();");
+    s.overwrite(
+      9,
+      12,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("// header\n")?;
+
+    let map = s.generate_map(GenerateDecodedMapOptions {
+      file: Some("output.js".to_owned()),
+      source_root: Some("./".to_owned()),
+      source: Some("input.js".to_owned()),
+      include_content: true,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    let parsed = SourceMap::from_url(map.to_url()?.as_str())?;
+
+    assert_eq!(parsed.to_string()?, map.to_string()?);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_parse_percent_encoded_url() -> Result {
+    let map = SourceMap::from_url(
+      "data:application/json,%7B%22version%22%3A3%2C%22mappings%22%3A%22AAAA%22%2C%22sources%22%3A%5B%22a.js%22%5D%7D",
+    )?;
+
+    assert_eq!(map.mappings, "AAAA");
+    assert_eq!(map.sources, vec![Some("a.js".to_owned())]);
+    assert!(map.names.is_empty());
+    assert!(map.sources_content.is_empty());
+    assert_eq!(map.file, None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_reject_invalid_input() {
+    assert_eq!(
+      SourceMap::from_json("{}").unwrap_err().error_type,
+      MagicStringErrorType::JSONSerializationError
+    );
+    assert_eq!(
+      SourceMap::from_json(r#"{"version":2,"mappings":"","sources":[]}"#)
+        .unwrap_err()
+        .error_type,
+      MagicStringErrorType::JSONSerializationError
+    );
+    assert_eq!(
+      SourceMap::from_url("https://example.com/index.js.map")
+        .unwrap_err()
+        .error_type,
+      MagicStringErrorType::DataUrlError
+    );
+    assert_eq!(
+      SourceMap::from_url("data:text/plain;base64,e30=")
+        .unwrap_err()
+        .error_type,
+      MagicStringErrorType::DataUrlError
+    );
+    assert_eq!(
+      SourceMap::from_url("data:application/json;base64,!!!")
+        .unwrap_err()
+        .error_type,
+      MagicStringErrorType::DataUrlError
+    );
+  }
+
+  #[test]
+  fn should_decode_mappings() -> Result {
+    let mut s = MagicString::new("function foo() {}\nfoo();");
+    s.overwrite(
+      9,
+      12,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("// header\n")?;
+
+    let map = s.generate_map(GenerateDecodedMapOptions {
+      file: Some("output.js".to_owned()),
+      source_root: Some("./".to_owned()),
+      source: Some("input.js".to_owned()),
+      include_content: true,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    let decoded_map = map.to_decoded_map()?;
+
+    assert_eq!(decoded_map.names, vec!["foo".to_owned()]);
+    assert_eq!(
+      decoded_map.mappings,
+      vec![
+        vec![],
+        vec![vec![0, 0, 0, 0], vec![9, 0, 0, 9, 0], vec![3, 0, 0, 3]],
+        vec![vec![0, 0, 1, -12]],
+      ]
+    );
+    assert_eq!(
+      SourceMap::new_from_decoded(decoded_map)?.mappings,
+      map.mappings
+    );
+
+    assert_eq!(decode_mappings("")?, vec![vec![]] as Vec<Vec<Vec<i64>>>);
+    assert_eq!(decode_mappings(";;A")?, vec![vec![], vec![], vec![vec![0]]]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_reject_malformed_mappings() {
+    assert_eq!(
+      decode_mappings("AAAA,G*AS").unwrap_err().error_type,
+      MagicStringErrorType::VlqInvalidBase64
+    );
+    // `g` has the continuation bit set, but nothing follows
+    assert_eq!(
+      decode_mappings("AAAg").unwrap_err().error_type,
+      MagicStringErrorType::VlqUnexpectedEof
+    );
+    assert_eq!(
+      decode_mappings("AAA").unwrap_err().error_type,
+      MagicStringErrorType::VlqUnexpectedEof
+    );
+    assert_eq!(
+      decode_mappings("AAAAAA").unwrap_err().error_type,
+      MagicStringErrorType::VlqUnexpectedEof
+    );
+  }
+
+  #[test]
+  fn should_reject_negative_absolute_values() {
+    // original column goes from 0 to -1
+    let err = decode_mappings("AAAA,CAAD").unwrap_err();
+    assert_eq!(err.error_type, MagicStringErrorType::VlqNegativeValue);
+    assert!(err.reason.unwrap().contains("original column"));
+
+    assert!(decode_mappings("D").is_err());
+    assert!(decode_mappings("ADAA").is_err());
+    assert!(decode_mappings("AAAA;AADA").is_err());
+    assert!(decode_mappings("AAAAA,CAAAD").is_err());
+    // the generated column is reset on each line, other fields carry over
+    assert!(decode_mappings("EAAA;AACC").is_ok());
+    assert!(decode_mappings("EAAA;DAAA").is_err());
+  }
+
+  #[test]
+  fn should_serialize_ignore_list_and_debug_id() -> Result {
+    let s = MagicString::new("abc");
+    let map = s.generate_map(GenerateDecodedMapOptions {
+      source: Some("vendor.js".to_owned()),
+      ignore_list: vec![0],
+      debug_id: Some("85314830-023f-4cf1-a267-535f4e37bb17".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    assert_eq!(
+      map.to_string()?,
+      r#"{"version":3,"mappings":"AAAA","names":[],"sources":["vendor.js"],"sourcesContent":[],"file":null,"x_google_ignoreList":[0],"debugId":"85314830-023f-4cf1-a267-535f4e37bb17"}"#
+    );
+    assert_eq!(
+      map.debug_id_comment(),
+      Some("//# debugId=85314830-023f-4cf1-a267-535f4e37bb17".to_owned())
+    );
+
+    let parsed = SourceMap::from_json(map.to_string()?.as_str())?;
+    assert_eq!(parsed.ignore_list, vec![0]);
+    assert_eq!(parsed.debug_id, map.debug_id);
+
+    let decoded_map = parsed.to_decoded_map()?;
+    assert_eq!(decoded_map.ignore_list, vec![0]);
+    assert_eq!(decoded_map.debug_id, map.debug_id);
+
+    // `DecodedMap` follows the same casing as `SourceMap`
+    assert_eq!(
+      serde_json::to_string(&decoded_map)?,
+      r#"{"file":null,"sources":["vendor.js"],"sourceRoot":null,"sourcesContent":[],"names":[],"mappings":[[[0,0,0,0]]],"x_google_ignoreList":[0],"debugId":"85314830-023f-4cf1-a267-535f4e37bb17"}"#
+    );
+
+    let legacy: DecodedMap = serde_json::from_str(
+      r#"{"file":null,"sources":["a.js"],"source_root":"src","sources_content":["abc"],"names":[],"mappings":[]}"#,
+    )?;
+    assert_eq!(legacy.source_root, Some("src".to_owned()));
+    assert_eq!(legacy.sources_content, vec![Some("abc".to_owned())]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_omit_empty_ignore_list_and_debug_id() -> Result {
+    let mut s = MagicString::new("function foo() {}\nfoo();");
+    s.overwrite(
+      9,
+      12,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("// header\n")?;
+
+    let map = s.generate_map(GenerateDecodedMapOptions {
+      file: Some("output.js".to_owned()),
+      source_root: Some("./".to_owned()),
+      source: Some("input.js".to_owned()),
+      include_content: true,
+      ..GenerateDecodedMapOptions::default()
+    })?;
+
+    assert!(map.ignore_list.is_empty());
+    assert_eq!(map.debug_id, None);
+    assert_eq!(map.debug_id_comment(), None);
+    assert!(!map.to_string()?.contains("x_google_ignoreList"));
+    assert!(!map.to_string()?.contains("debugId"));
+
+    Ok(())
+  }
+}
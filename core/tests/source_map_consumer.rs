@@ -0,0 +1,231 @@
+#[cfg(test)]
+mod source_map_consumer {
+  use magic_string::{
+    Bias, Bundle, BundleOptions, GenerateDecodedMapOptions, GeneratedPosition, MagicString,
+    MagicStringErrorType, MappingItem, OriginalPosition, OverwriteOptions, Result, SourceMap,
+    SourceMapConsumer,
+  };
+
+  fn original(line: u32, column: u32, name: Option<&str>) -> Option<OriginalPosition> {
+    Some(OriginalPosition {
+      source: Some("input.js".to_owned()),
+      line,
+      column,
+      name: name.map(|n| n.to_owned()),
+    })
+  }
+
+  #[test]
+  fn should_find_original_positions() -> Result {
+    let mut s = MagicString::new("function foo() {}\nfoo();");
+    s.overwrite(
+      9,
+      12,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("// header\n")?;
+
+    // function bar() {}  =>  AAAA,SAASA,GAAG
+    // bar();             =>  AACZ
+    let consumer = SourceMapConsumer::new(&s.generate_map(GenerateDecodedMapOptions {
+      source: Some("input.js".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    })?)?;
+
+    assert_eq!(
+      consumer.original_position_for(1, 0, Bias::GreatestLowerBound),
+      original(0, 0, None)
+    );
+    assert_eq!(
+      consumer.original_position_for(1, 9, Bias::GreatestLowerBound),
+      original(0, 9, Some("foo"))
+    );
+    assert_eq!(
+      consumer.original_position_for(2, 0, Bias::LeastUpperBound),
+      original(1, 0, None)
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_respect_bias() -> Result {
+    let mut s = MagicString::new("function foo() {}\nfoo();");
+    s.overwrite(
+      9,
+      12,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("// header\n")?;
+
+    // function bar() {}  =>  AAAA,SAASA,GAAG
+    // bar();             =>  AACZ
+    let consumer = SourceMapConsumer::new(&s.generate_map(GenerateDecodedMapOptions {
+      source: Some("input.js".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    })?)?;
+
+    assert_eq!(
+      consumer.original_position_for(1, 10, Bias::GreatestLowerBound),
+      original(0, 9, Some("foo"))
+    );
+    assert_eq!(
+      consumer.original_position_for(1, 10, Bias::LeastUpperBound),
+      original(0, 12, None)
+    );
+    assert_eq!(
+      consumer.original_position_for(1, 100, Bias::GreatestLowerBound),
+      original(0, 12, None)
+    );
+    assert_eq!(
+      consumer.original_position_for(1, 100, Bias::LeastUpperBound),
+      None
+    );
+    assert_eq!(
+      consumer.original_position_for(2, 3, Bias::LeastUpperBound),
+      None
+    );
+
+    // the header is not mapped at all
+    assert_eq!(
+      consumer.original_position_for(0, 0, Bias::GreatestLowerBound),
+      None
+    );
+    assert_eq!(
+      consumer.original_position_for(10, 0, Bias::GreatestLowerBound),
+      None
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_find_generated_positions() -> Result {
+    let mut s = MagicString::new("function foo() {}\nfoo();");
+    s.overwrite(
+      9,
+      12,
+      "bar",
+      OverwriteOptions {
+        store_name: true,
+        ..OverwriteOptions::default()
+      },
+    )?;
+    s.prepend("// header\n")?;
+
+    // function bar() {}  =>  AAAA,SAASA,GAAG
+    // bar();             =>  AACZ
+    let consumer = SourceMapConsumer::new(&s.generate_map(GenerateDecodedMapOptions {
+      source: Some("input.js".to_owned()),
+      ..GenerateDecodedMapOptions::default()
+    })?)?;
+
+    assert_eq!(
+      consumer.generated_positions_for("input.js", 0, 9),
+      vec![GeneratedPosition { line: 1, column: 9 }]
+    );
+    // falls back to the closest column after it on the same line
+    assert_eq!(
+      consumer.generated_positions_for("input.js", 0, 10),
+      vec![GeneratedPosition {
+        line: 1,
+        column: 12
+      }]
+    );
+    assert_eq!(consumer.generated_positions_for("input.js", 0, 13), vec![]);
+    assert_eq!(consumer.generated_positions_for("input.js", 2, 0), vec![]);
+    assert_eq!(consumer.generated_positions_for("other.js", 0, 0), vec![]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_find_all_generated_positions() -> Result {
+    let mut bundle = Bundle::new(BundleOptions::default());
+    bundle.add_source(Some("a.js"), MagicString::new("const a = 1"))?;
+    bundle.add_source(Some("b.js"), MagicString::new("const b = 2"))?;
+    bundle.add_source(Some("a.js"), MagicString::new("const a = 1"))?;
+
+    let consumer = SourceMapConsumer::new_from_decoded(
+      &bundle.generate_decoded_map(GenerateDecodedMapOptions::default())?,
+    );
+
+    assert_eq!(
+      consumer.generated_positions_for("a.js", 0, 0),
+      vec![
+        GeneratedPosition { line: 0, column: 0 },
+        GeneratedPosition { line: 2, column: 0 }
+      ]
+    );
+    assert_eq!(
+      consumer.generated_positions_for("b.js", 0, 0),
+      vec![GeneratedPosition { line: 1, column: 0 }]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_iterate_over_each_mapping() -> Result {
+    let consumer = SourceMapConsumer::new(&SourceMap::from_json(
+      r#"{"version":3,"mappings":"A,CAAAA;AACC","names":["foo"],"sources":["input.js"]}"#,
+    )?)?;
+
+    let mut mappings: Vec<MappingItem> = vec![];
+    consumer.each_mapping(|mapping| mappings.push(mapping));
+
+    assert_eq!(
+      mappings,
+      vec![
+        MappingItem {
+          generated_line: 0,
+          generated_column: 0,
+          source: None,
+          original_line: None,
+          original_column: None,
+          name: None,
+        },
+        MappingItem {
+          generated_line: 0,
+          generated_column: 1,
+          source: Some("input.js".to_owned()),
+          original_line: Some(0),
+          original_column: Some(0),
+          name: Some("foo".to_owned()),
+        },
+        MappingItem {
+          generated_line: 1,
+          generated_column: 0,
+          source: Some("input.js".to_owned()),
+          original_line: Some(1),
+          original_column: Some(1),
+          name: None,
+        },
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_reject_mappings_with_negative_positions() -> Result {
+    let map = SourceMap::from_json(
+      r#"{"version":3,"mappings":"AAAA,CAAD","names":[],"sources":["input.js"]}"#,
+    )?;
+
+    assert_eq!(
+      SourceMapConsumer::new(&map).unwrap_err().error_type,
+      MagicStringErrorType::VlqNegativeValue
+    );
+
+    Ok(())
+  }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod thread {
+  use std::thread;
+
+  use magic_string::{
+    Bundle, BundleOptions, GenerateDecodedMapOptions, MagicString, OverwriteOptions, Result,
+  };
+
+  fn assert_send_sync<T: Send + Sync>() {}
+
+  #[test]
+  fn should_be_send_and_sync() {
+    assert_send_sync::<MagicString>();
+    assert_send_sync::<Bundle>();
+  }
+
+  #[test]
+  fn should_be_moved_across_threads() -> Result {
+    let s = thread::spawn(|| -> Result<MagicString> {
+      let mut s = MagicString::new("abcdefghijkl");
+      s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+      s._move(0, 3, 12)?;
+
+      Ok(s)
+    })
+    .join()
+    .unwrap()?;
+
+    assert_eq!(s.to_string(), "DEFghijklabc");
+
+    let mut bundle = Bundle::new(BundleOptions::default());
+    bundle.add_source(Some("a.js"), s)?;
+
+    let map = thread::spawn(move || bundle.generate_map(GenerateDecodedMapOptions::default()))
+      .join()
+      .unwrap()?;
+    assert_eq!(map.mappings, "AAAG,GAAG,MAAN");
+
+    Ok(())
+  }
+}
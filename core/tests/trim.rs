@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod trim {
-  use magic_string::{MagicString, OverwriteOptions, Result};
+  use magic_string::{MagicString, OverwriteOptions, Regex, Result};
 
   #[test]
   fn should_trim_original_content() -> Result {
@@ -96,6 +96,130 @@ mod trim {
 
     Ok(())
   }
+
+  #[test]
+  fn should_trim_multi_character_patterns_across_chunks() -> Result {
+    let mut s = MagicString::new("\n\r\nabc\r\n\r");
+    s.prepend("\r\n\r")?;
+    s.append("\n\r\n")?;
+    s.remove(4, 5)?;
+    s.trim(Some("\r\n"))?;
+
+    assert_eq!(s.to_string(), "ac");
+
+    let mut s = MagicString::new("; ;\n;let a = 1; ;");
+    s.overwrite(5, 9, " ;", OverwriteOptions::default())?;
+    s.trim(Some("\\s*;"))?;
+
+    assert_eq!(s.to_string(), "a = 1");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_trim_non_ascii_contents() -> Result {
+    let mut s = MagicString::new("\u{3000}中文 中文\u{3000}");
+    s.prepend("😀 ")?;
+    s.append("😀😀")?;
+    s.trim(Some("\\s|😀"))?;
+
+    assert_eq!(s.to_string(), "中文 中文");
+
+    let mut s = MagicString::new("中文中文abc中文");
+    s.trim(Some("中文"))?;
+
+    assert_eq!(s.to_string(), "abc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_trim_with_precompiled_regex() -> Result {
+    let pattern = Regex::new("\\s*//[^\n]*\n")?;
+
+    let mut s = MagicString::new("// a\n  // b\nabc\n");
+    s.append("// c\n")?;
+    s.trim_with(&pattern)?;
+
+    assert_eq!(s.to_string(), "abc");
+
+    let mut s = MagicString::new("// a\nabc");
+    s.trim_start_with(pattern)?;
+
+    assert_eq!(s.to_string(), "abc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_keep_the_flags_of_precompiled_regex() -> Result {
+    let pattern = regex::RegexBuilder::new("x")
+      .case_insensitive(true)
+      .build()?;
+
+    let mut s = MagicString::new("XXabcXX");
+    s.trim_with(&pattern)?;
+
+    assert_eq!(s.to_string(), "abc");
+
+    let mut s = MagicString::new("abcxXx");
+    s.prepend("a")?;
+    s.trim_end_with(pattern)?;
+
+    assert_eq!(s.to_string(), "aabc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_trim_with_char_predicate() -> Result {
+    let mut s = MagicString::new("0012300");
+    s.prepend("  0")?;
+    s.trim_with(|c: char| c == '0' || c.is_whitespace())?;
+
+    assert_eq!(s.to_string(), "123");
+
+    let mut s = MagicString::new("——abc——");
+    s.trim_end_with(|c: char| c == '—')?;
+
+    assert_eq!(s.to_string(), "——abc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_for_invalid_patterns() {
+    let mut s = MagicString::new("abc");
+
+    assert!(s.trim(Some("(")).is_err());
+  }
+
+  #[test]
+  fn should_trim_overlapping_and_odd_length_repetitions() -> Result {
+    let mut s = MagicString::new("xaaa");
+    s.trim_end(Some("aa"))?;
+
+    assert_eq!(s.to_string(), "xa");
+
+    let mut s = MagicString::new("aaax");
+    s.trim_start(Some("aa"))?;
+
+    assert_eq!(s.to_string(), "ax");
+
+    let mut s = MagicString::new("aaa");
+    s.prepend("aa")?;
+    s.trim(Some("aa"))?;
+
+    assert_eq!(s.to_string(), "a");
+
+    let mut s = MagicString::new("abab");
+    s.append("a")?;
+    s.trim_end_with(&Regex::new("aba")?)?;
+
+    assert_eq!(s.to_string(), "ab");
+
+    Ok(())
+  }
 }
 
 #[cfg(test)]
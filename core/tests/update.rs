@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod update {
+  use magic_string::{GenerateDecodedMapOptions, MagicString, Result, UpdateOptions};
+
+  #[test]
+  fn should_replace_characters() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.update(5, 8, "FGH", UpdateOptions::default())?;
+    assert_eq!(s.to_string(), "abcdeFGHijkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_preserve_interior_inserts_by_default() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.append_left(1, "&")?;
+    s.prepend_right(1, "^")?;
+    s.append_left(3, "!")?;
+    s.prepend_right(3, "?")?;
+    s.update(1, 3, "...", UpdateOptions::default())?;
+
+    assert_eq!(s.to_string(), "a&^...!?defghijkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_replace_interior_inserts_with_overwrite_true() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.append_left(1, "&")?;
+    s.prepend_right(1, "^")?;
+    s.append_left(3, "!")?;
+    s.prepend_right(3, "?")?;
+    s.update(
+      1,
+      3,
+      "...",
+      UpdateOptions {
+        overwrite: true,
+        ..UpdateOptions::default()
+      },
+    )?;
+
+    assert_eq!(s.to_string(), "a&...?defghijkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_keep_prepended_and_appended_content_across_chunks() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.append_left(6, "X")?;
+    s.update(3, 9, "DEFGHI", UpdateOptions::default())?;
+
+    assert_eq!(s.to_string(), "abcDEFGHIXjkl");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_return_an_error_if_overlapping_replacements_are_attempted() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.update(7, 11, "xx", UpdateOptions::default())?;
+    assert!(s.update(8, 12, "yy", UpdateOptions::default()).is_err());
+    assert!(s.update(3, 3, "yy", UpdateOptions::default()).is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_store_names() -> Result {
+    let mut s = MagicString::new("function Foo () {}");
+
+    s.update(
+      9,
+      12,
+      "Bar",
+      UpdateOptions {
+        store_name: true,
+        ..UpdateOptions::default()
+      },
+    )?;
+
+    let map = s.generate_map(GenerateDecodedMapOptions::default())?;
+
+    assert_eq!(map.names, vec!["Foo".to_owned()]);
+    assert_eq!(map.mappings, "AAAA,SAASA,GAAG");
+
+    Ok(())
+  }
+}
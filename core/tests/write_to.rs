@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod write_to {
+  use magic_string::{MagicString, OverwriteOptions, Result};
+
+  #[test]
+  fn should_write_the_same_string_as_to_string() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.prepend("/* 😀 */")?;
+    s.append("\n")?;
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+    s.append_left(9, "(")?;
+    s.prepend_right(9, "[")?;
+    s.remove(10, 11)?;
+    s._move(0, 3, 12)?;
+
+    let mut output = String::new();
+    s.write_to(&mut output).unwrap();
+
+    assert_eq!(output, "/* 😀 */DEFghi([jlabc\n");
+    assert_eq!(output, s.to_string());
+    assert_eq!(output, format!("{}", s));
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_calculate_the_length_in_bytes() -> Result {
+    let mut s = MagicString::new("abcdefghijkl");
+
+    s.prepend("/* 😀 */")?;
+    s.append("\n")?;
+    s.overwrite(3, 6, "DEF", OverwriteOptions::default())?;
+    s.append_left(9, "(")?;
+    s.prepend_right(9, "[")?;
+    s.remove(10, 11)?;
+    s._move(0, 3, 12)?;
+
+    assert_eq!(s.len(), s.to_string().len());
+    assert_eq!(MagicString::new("").len(), 0);
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_treat_whitespace_only_output_as_empty() -> Result {
+    let mut s = MagicString::new("\t");
+
+    s.append_left(1, " \n")?;
+    s.prepend("\u{a0}")?;
+    assert!(s.is_empty());
+
+    s.append_right(0, "a")?;
+    assert!(!s.is_empty());
+
+    Ok(())
+  }
+}
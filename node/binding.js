@@ -216,6 +216,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { MagicString } = nativeBinding
+const { MagicString, Bundle } = nativeBinding
 
 module.exports.MagicString = MagicString
+module.exports.Bundle = Bundle
@@ -17,6 +17,8 @@ export interface DecodedMap {
   sourcesContent: Array<string | undefined | null>
   names: Array<string>
   mappings: Array<Array<Array<number>>>
+  x_google_ignoreList?: Array<number> | undefined | null
+  debugId?: string | undefined | null
 }
 /** Only for .d.ts generation */
 export interface GenerateDecodedMapOptions {
@@ -24,11 +26,34 @@ export interface GenerateDecodedMapOptions {
   sourceRoot?: string | undefined | null
   source?: string | undefined | null
   includeContent: boolean
-  hires: boolean
+  hires: Hires
+  columnUnit: ColumnUnit
+  ignoreList: Array<number>
+  debugId?: string | undefined | null
 }
+/** Unit in which sourcemap columns are counted, defaults to `'Utf16'` */
+export type ColumnUnit = 'Utf16' | 'Utf8' | 'Char'
+/** Resolution of mappings for unedited content, `'boundary'` maps the start of each word */
+export type Hires = boolean | 'boundary'
 /** Only for .d.ts generation */
 export interface OverwriteOptions {
   contentOnly: boolean
+  storeName: boolean
+}
+/** Only for .d.ts generation */
+export interface UpdateOptions {
+  overwrite: boolean
+  storeName: boolean
+}
+/** Only for .d.ts generation */
+export interface IndentOptions {
+  exclude?: Array<Array<number>> | undefined | null
+  indentStart?: boolean | undefined | null
+}
+/** Only for .d.ts generation */
+export interface BundleOptions {
+  intro?: string | undefined | null
+  separator?: string | undefined | null
 }
 export class MagicString {
   constructor(originalStr: string)
@@ -44,12 +69,21 @@ export class MagicString {
     content: string,
     options?: OverwriteOptions,
   ): this
+  update(
+    start: number,
+    end: number,
+    content: string,
+    options?: UpdateOptions,
+  ): this
+  reset(start: number, end: number): this
   trim(pattern?: string | undefined | null): this
   trimStart(pattern?: string | undefined | null): this
   trimEnd(pattern?: string | undefined | null): this
   trimLines(): this
+  indent(indentStr?: string, options?: Partial<IndentOptions>): this
   remove(start: number, end: number): this
   move(start: number, end: number, index: number): this
+  addSourcemapLocation(index: number): this
   isEmpty(): boolean
   generateMap(options?: Partial<GenerateDecodedMapOptions>): {
     toString: () => string
@@ -62,9 +96,38 @@ export class MagicString {
       names: string[]
       mappings: string
       sourceRoot?: string
+      x_google_ignoreList?: number[]
+      debugId?: string
     }
   }
   generateDecodedMap(options?: Partial<GenerateDecodedMapOptions>): DecodedMap
   toString(): string
   length(): number
+  originalIndexFor(generatedIndex: number): number | null
+  generatedIndexFor(originalIndex: number): number | null
+}
+export class Bundle {
+  constructor(options?: BundleOptions | undefined | null)
+  addSource(
+    source: MagicString | { filename?: string; content: MagicString },
+  ): this
+  append(input: string, options?: { separator?: string }): this
+  prepend(input: string): this
+  generateMap(options?: Partial<GenerateDecodedMapOptions>): {
+    toString: () => string
+    toUrl: () => string
+    toMap: () => {
+      version: number
+      file?: string
+      sources: string[]
+      sourcesContent: string[]
+      names: string[]
+      mappings: string
+      sourceRoot?: string
+      x_google_ignoreList?: number[]
+      debugId?: string
+    }
+  }
+  generateDecodedMap(options?: Partial<GenerateDecodedMapOptions>): DecodedMap
+  toString(): string
 }
@@ -1,13 +1,98 @@
-const { MagicString: MagicStringNative } = require('./binding')
+const {
+  MagicString: MagicStringNative,
+  Bundle: BundleNative,
+} = require('./binding')
 
 module.exports.MagicString = class MagicString extends MagicStringNative {
   overwrite(start, end, content, options) {
     options = {
       contentOnly: false,
+      storeName: false,
       ...options
     }
     return super.overwrite(start, end, content, options)
   }
+  update(start, end, content, options) {
+    options = {
+      overwrite: false,
+      storeName: false,
+      ...options,
+    }
+    return super.update(start, end, content, options)
+  }
+  indent(indentStr, options) {
+    if (typeof indentStr === 'object' && indentStr !== null) {
+      options = indentStr
+      indentStr = undefined
+    }
+    if (options && typeof options.exclude?.[0] === 'number') {
+      options = {
+        ...options,
+        exclude: [options.exclude],
+      }
+    }
+    return super.indent(indentStr, options)
+  }
+  generateMap(options) {
+    options = {
+      file: null,
+      source: null,
+      sourceRoot: null,
+      includeContent: false,
+      hires: false,
+      columnUnit: 'Utf16',
+      ignoreList: [],
+      debugId: null,
+      ...options,
+    }
+
+    const toString = () => super.toSourcemapString(options)
+    const toUrl = () => super.toSourcemapUrl(options)
+    const toMap = () => JSON.parse(toString(options))
+
+    return {
+      toString,
+      toUrl,
+      toMap,
+    }
+  }
+  generateDecodedMap(options) {
+    options = {
+      file: null,
+      source: null,
+      sourceRoot: null,
+      includeContent: false,
+      hires: false,
+      columnUnit: 'Utf16',
+      ignoreList: [],
+      debugId: null,
+      ...options,
+    }
+
+    return JSON.parse(super.generateDecodedMap(options))
+  }
+  toSourcemapString() {
+    throw new Error(
+      '[magic-string] This is an internal API, you may refer to `generateMap`',
+    )
+  }
+  toSourcemapUrl() {
+    throw new Error(
+      '[magic-string] This is an internal API, you may refer to `generateMap`',
+    )
+  }
+}
+
+module.exports.Bundle = class Bundle extends BundleNative {
+  addSource(source) {
+    if (source instanceof MagicStringNative) {
+      return super.addSource(null, source)
+    }
+    return super.addSource(source.filename ?? null, source.content)
+  }
+  append(input, options) {
+    return super.append(input, options?.separator ?? null)
+  }
   generateMap(options) {
     options = {
       file: null,
@@ -15,6 +100,9 @@ module.exports.MagicString = class MagicString extends MagicStringNative {
       sourceRoot: null,
       includeContent: false,
       hires: false,
+      columnUnit: 'Utf16',
+      ignoreList: [],
+      debugId: null,
       ...options,
     }
 
@@ -35,6 +123,9 @@ module.exports.MagicString = class MagicString extends MagicStringNative {
       sourceRoot: null,
       includeContent: false,
       hires: false,
+      columnUnit: 'Utf16',
+      ignoreList: [],
+      debugId: null,
       ...options,
     }
 
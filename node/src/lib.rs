@@ -18,7 +18,13 @@ pub fn create_external<T>(value: T) -> External<T> {
 impl MagicString {
   #[napi(constructor)]
   pub fn new(original_str: String) -> Self {
-    MagicString(magic_string::MagicString::new(original_str.as_str()))
+    // indices passed from JavaScript are counted in UTF-16 code units
+    MagicString(magic_string::MagicString::new_with_options(
+      original_str.as_str(),
+      magic_string::MagicStringOptions {
+        index_unit: magic_string::IndexUnit::Utf16,
+      },
+    ))
   }
 
   #[napi]
@@ -74,6 +80,29 @@ impl MagicString {
     Ok(self)
   }
 
+  #[napi(ts_args_type = r"
+    start: number,
+    end: number,
+    content: string,
+    options?: UpdateOptions
+  ")]
+  pub fn update(
+    &mut self,
+    start: i64,
+    end: i64,
+    content: String,
+    options: magic_string::UpdateOptions,
+  ) -> Result<&Self> {
+    self.0.update(start, end, content.as_str(), options)?;
+    Ok(self)
+  }
+
+  #[napi]
+  pub fn reset(&mut self, start: i64, end: i64) -> Result<&Self> {
+    self.0.reset(start, end)?;
+    Ok(self)
+  }
+
   #[napi]
   pub fn trim(&mut self, pattern: Option<String>) -> Result<&Self> {
     self.0.trim(pattern.as_deref())?;
@@ -98,6 +127,21 @@ impl MagicString {
     Ok(self)
   }
 
+  #[napi(ts_args_type = r"
+    indentStr?: string,
+    options?: Partial<IndentOptions>
+  ")]
+  pub fn indent(
+    &mut self,
+    indent_str: Option<String>,
+    options: Option<magic_string::IndentOptions>,
+  ) -> Result<&Self> {
+    self
+      .0
+      .indent(indent_str.as_deref(), options.unwrap_or_default())?;
+    Ok(self)
+  }
+
   #[napi]
   pub fn remove(&mut self, start: i64, end: i64) -> Result<&Self> {
     self.0.remove(start, end)?;
@@ -109,6 +153,12 @@ impl MagicString {
     self.0._move(start, end, index)?;
     Ok(self)
   }
+  #[napi]
+  pub fn add_sourcemap_location(&mut self, index: u32) -> Result<&Self> {
+    self.0.add_sourcemap_location(index)?;
+    Ok(self)
+  }
+
   #[napi]
   pub fn is_empty(&self) -> Result<bool> {
     Ok(self.0.is_empty())
@@ -127,6 +177,8 @@ impl MagicString {
       names: string[];
       mappings: string;
       sourceRoot?: string;
+      x_google_ignoreList?: number[];
+      debugId?: string;
     }
     }"
   )]
@@ -176,7 +228,111 @@ impl MagicString {
 
   #[napi]
   pub fn length(&self) -> u32 {
-    self.0.len() as u32
+    self.0.len_in_index_unit() as u32
+  }
+
+  #[napi]
+  pub fn original_index_for(&self, generated_index: u32) -> Option<u32> {
+    self.0.original_index_for(generated_index as usize)
+  }
+
+  #[napi]
+  pub fn generated_index_for(&self, original_index: u32) -> Option<u32> {
+    self
+      .0
+      .generated_index_for(original_index)
+      .map(|index| index as u32)
+  }
+}
+
+#[napi]
+pub struct Bundle(magic_string::Bundle);
+
+#[napi]
+impl Bundle {
+  #[napi(constructor)]
+  pub fn new(options: Option<magic_string::BundleOptions>) -> Self {
+    Bundle(magic_string::Bundle::new(options.unwrap_or_default()))
+  }
+
+  #[napi(ts_args_type = "source: MagicString | { filename?: string, content: MagicString }")]
+  pub fn add_source(&mut self, filename: Option<String>, content: &MagicString) -> Result<&Self> {
+    self.0.add_source(filename.as_deref(), content.0.clone())?;
+    Ok(self)
+  }
+
+  #[napi(ts_args_type = "input: string, options?: { separator?: string }")]
+  pub fn append(&mut self, input: String, separator: Option<String>) -> Result<&Self> {
+    self.0.append(input.as_str(), separator.as_deref())?;
+    Ok(self)
+  }
+
+  #[napi]
+  pub fn prepend(&mut self, input: String) -> Result<&Self> {
+    self.0.prepend(input.as_str())?;
+    Ok(self)
+  }
+
+  #[napi(
+    ts_args_type = "options?: Partial<GenerateDecodedMapOptions>",
+    ts_return_type = r"{
+    toString: () => string;
+    toUrl: () => string;
+    toMap: () => {
+      version: number;
+      file?: string;
+      sources: string[];
+      sourcesContent: string[];
+      names: string[];
+      mappings: string;
+      sourceRoot?: string;
+      x_google_ignoreList?: number[];
+      debugId?: string;
+    }
+    }"
+  )]
+  pub fn generate_map(&self) -> Result<()> {
+    // only for .d.ts generation
+    Ok(())
+  }
+
+  #[napi(skip_typescript)]
+  pub fn to_sourcemap_string(
+    &self,
+    options: Option<magic_string::GenerateDecodedMapOptions>,
+  ) -> Result<String> {
+    Ok(
+      self
+        .0
+        .generate_map(options.unwrap_or_default())?
+        .to_string()?,
+    )
+  }
+
+  #[napi(skip_typescript)]
+  pub fn to_sourcemap_url(
+    &self,
+    options: Option<magic_string::GenerateDecodedMapOptions>,
+  ) -> Result<String> {
+    Ok(self.0.generate_map(options.unwrap_or_default())?.to_url()?)
+  }
+
+  #[napi(
+    ts_args_type = "options?: Partial<GenerateDecodedMapOptions>",
+    ts_return_type = "DecodedMap"
+  )]
+  pub fn generate_decoded_map(
+    &self,
+    options: Option<magic_string::GenerateDecodedMapOptions>,
+  ) -> Result<String> {
+    let decoded = self.0.generate_decoded_map(options.unwrap_or_default())?;
+    Ok(serde_json::to_string(&decoded)?)
+  }
+
+  #[napi]
+  #[allow(clippy::inherent_to_string)]
+  pub fn to_string(&self) -> String {
+    self.0.to_string()
   }
 }
 
@@ -189,6 +345,9 @@ pub struct DecodedMap {
   pub sources_content: Vec<Option<String>>,
   pub names: Vec<String>,
   pub mappings: Vec<Vec<Vec<i64>>>,
+  #[napi(js_name = "x_google_ignoreList")]
+  pub ignore_list: Option<Vec<u32>>,
+  pub debug_id: Option<String>,
 }
 
 /// Only for .d.ts generation
@@ -198,10 +357,32 @@ pub struct GenerateDecodedMapOptions {
   pub source_root: Option<String>,
   pub source: Option<String>,
   pub include_content: bool,
-  pub hires: bool,
+  pub hires: magic_string::Hires,
+  pub column_unit: magic_string::ColumnUnit,
+  pub ignore_list: Vec<u32>,
+  pub debug_id: Option<String>,
 }
 /// Only for .d.ts generation
 #[napi(object)]
 pub struct OverwriteOptions {
   pub content_only: bool,
+  pub store_name: bool,
+}
+/// Only for .d.ts generation
+#[napi(object)]
+pub struct UpdateOptions {
+  pub overwrite: bool,
+  pub store_name: bool,
+}
+/// Only for .d.ts generation
+#[napi(object)]
+pub struct IndentOptions {
+  pub exclude: Option<Vec<Vec<u32>>>,
+  pub indent_start: Option<bool>,
+}
+/// Only for .d.ts generation
+#[napi(object)]
+pub struct BundleOptions {
+  pub intro: Option<String>,
+  pub separator: Option<String>,
 }
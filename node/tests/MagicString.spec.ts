@@ -238,6 +238,16 @@ describe('MagicString', () => {
       assert.equal(loc.column, 10)
     })
 
+    it('should generate a sourcemap at word boundaries when hires = boundary', () => {
+      const s = new MagicString('foo.bar(baz)')
+
+      const map = s.generateMap({
+        hires: 'boundary',
+      })
+
+      assert.equal(map.toMap().mappings, 'AAAA,GAAG,CAAC,GAAG,CAAC,GAAG')
+    })
+
     it('should generate a correct sourcemap for prepend content when hires = false', () => {
       const s = new MagicString('x\nq')
 
@@ -354,22 +364,22 @@ describe('MagicString', () => {
       assert.deepEqual(m1, m2)
     })
 
-    // it('should recover original names', () => {
-    //   const s = new MagicString('function Foo () {}')
+    it('should recover original names', () => {
+      const s = new MagicString('function Foo () {}')
 
-    //   s.overwrite(9, 12, 'Bar', { storeName: true })
+      s.overwrite(9, 12, 'Bar', { storeName: true })
 
-    //   const map = s.generateMap({
-    //     file: 'output.js',
-    //     source: 'input.js',
-    //     includeContent: true,
-    //   })
+      const map = s.generateMap({
+        file: 'output.js',
+        source: 'input.js',
+        includeContent: true,
+      }).toMap()
 
-    //   const smc = new SourceMapConsumer(map)
+      const smc = new SourceMapConsumer(map)
 
-    //   const loc = smc.originalPositionFor({ line: 1, column: 9 })
-    //   assert.equal(loc.name, 'Foo')
-    // })
+      const loc = smc.originalPositionFor({ line: 1, column: 9 })
+      assert.equal(loc.name, 'Foo')
+    })
 
     // it('should generate one segment per replacement', () => {
     //   const s = new MagicString('var answer = 42')
@@ -843,6 +853,15 @@ describe('overwrite', () => {
     assert.equal(s.toString(), 'abcdeFGHijkl')
   })
 
+  it('should use JavaScript string indices', () => {
+    const source = "const s = '中文😀'; foo();"
+    const s = new MagicString(source)
+
+    s.overwrite(source.indexOf('foo'), source.indexOf('foo') + 3, 'bar')
+    s.overwrite(source.indexOf('文'), source.indexOf("'; "), '字')
+    assert.equal(s.toString(), "const s = '中字'; bar();")
+  })
+
   it('should throw an error if overlapping replacements are attempted', () => {
     const s = new MagicString('abcdefghijkl')
 
@@ -1348,6 +1367,21 @@ describe('isEmpty', () => {
   })
 })
 
+describe('length', () => {
+  it('should count UTF-16 code units', () => {
+    const s = new MagicString('中文😀abc')
+
+    assert.equal(s.length(), 7)
+
+    s.remove(2, 4)
+    s.prepend('é')
+    s.overwrite(4, 5, '😀😀')
+
+    assert.equal(s.toString(), 'é中文😀😀bc')
+    assert.equal(s.length(), s.toString().length)
+  })
+})
+
 // Original length implementation does not count `intro / outro`
 // describe('length', () => {
 //   it('should support length', () => {